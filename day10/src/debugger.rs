@@ -0,0 +1,209 @@
+use std::io::{self, BufRead, Write};
+
+use crate::{Cpu, Instruction, MachineState, Screen};
+
+const HELP: &str = "\
+commands:
+  step [N]        advance N cycles (default 1), ignoring breakpoints
+  cycle N         run until cycle N is reached
+  instr N         run until instruction N is about to start
+  continue        run until a breakpoint is hit or the program ends
+  break X         stop whenever the X register changes to X
+  delete X        remove the breakpoint on X
+  breaks          list breakpoints
+  state           show the current machine state
+  screen          show the pixels drawn so far
+  help            show this message
+  quit            leave the debugger";
+
+enum Stop {
+    Reached,
+    Breakpoint(i32),
+    Finished,
+}
+
+/// Runs a program under the control of commands read line by line from
+/// `input`, writing everything it reports to `output`.
+struct Debugger<'a> {
+    cpu: Cpu<'a>,
+    screen: Screen,
+    last_state: Option<MachineState>,
+    breakpoints: Vec<i32>,
+}
+
+impl<'a> Debugger<'a> {
+    fn new(program: &'a [Instruction]) -> Self {
+        Debugger {
+            cpu: Cpu::new(program),
+            screen: Screen::new(6, 40),
+            last_state: None,
+            breakpoints: vec![],
+        }
+    }
+
+    fn tick(&mut self) -> Option<MachineState> {
+        let state = self.cpu.next()?;
+        self.screen.draw(state);
+        self.last_state = Some(state);
+        Some(state)
+    }
+
+    fn run_until(&mut self, done: impl Fn(&Cpu) -> bool) -> Stop {
+        while !done(&self.cpu) {
+            let previous = self.last_state.map(|s| s.register_value);
+            let state = match self.tick() {
+                Some(state) => state,
+                None => return Stop::Finished,
+            };
+
+            let x = state.register_value;
+            if previous != Some(x) && self.breakpoints.contains(&x) {
+                return Stop::Breakpoint(x);
+            }
+        }
+
+        Stop::Reached
+    }
+
+    fn describe_state(&self) -> String {
+        let during = match self.last_state {
+            Some(state) => format!("cycle {}: X={}", state.cycle, state.register_value),
+            None => "not started".to_string(),
+        };
+        let next = match self.cpu.program.get(self.cpu.instruction_pointer) {
+            Some(instruction) => format!(
+                "instruction {} ({}), {}/{} cycles done",
+                self.cpu.instruction_pointer,
+                instruction,
+                self.cpu.cycles_in_instruction,
+                instruction.operation.cycles,
+            ),
+            None => "program finished".to_string(),
+        };
+
+        format!("{}; X after cycle={}; {}", during, self.cpu.register_value(), next)
+    }
+
+    fn describe_stop(&self, stop: Stop) -> String {
+        match stop {
+            Stop::Reached => self.describe_state(),
+            Stop::Breakpoint(x) => format!("breakpoint X={} hit, {}", x, self.describe_state()),
+            Stop::Finished => format!("program finished, {}", self.describe_state()),
+        }
+    }
+
+    fn execute(&mut self, command: &str, argument: Option<i32>) -> Result<String, String> {
+        let required = || argument.ok_or_else(|| format!("{} expects a number", command));
+        let count = |n: i32| u32::try_from(n).map_err(|_| format!("{} expects a number that is not negative", command));
+        let reply = match command {
+            "step" | "s" => {
+                let n = count(argument.unwrap_or(1))?;
+                let mut stop = Stop::Reached;
+                for _ in 0..n {
+                    if self.tick().is_none() {
+                        stop = Stop::Finished;
+                        break;
+                    }
+                }
+                self.describe_stop(stop)
+            },
+            "cycle" => {
+                let target = count(required()?)? as i32;
+                let stop = self.run_until(|cpu| cpu.state.cycle >= target);
+                self.describe_stop(stop)
+            },
+            "instr" => {
+                let target = count(required()?)? as usize;
+                let stop = self.run_until(|cpu| cpu.instruction_pointer >= target && cpu.cycles_in_instruction == 0);
+                self.describe_stop(stop)
+            },
+            "continue" | "c" => {
+                let stop = self.run_until(|_| false);
+                self.describe_stop(stop)
+            },
+            "break" | "b" => {
+                let x = required()?;
+                if !self.breakpoints.contains(&x) {
+                    self.breakpoints.push(x);
+                }
+                format!("breakpoint set on X={}", x)
+            },
+            "delete" => {
+                let x = required()?;
+                self.breakpoints.retain(|b| *b != x);
+                format!("breakpoint on X={} removed", x)
+            },
+            "breaks" => format!("breakpoints: {:?}", self.breakpoints),
+            "state" => self.describe_state(),
+            "screen" => self.screen.render(),
+            "help" => HELP.to_string(),
+            _ => return Err(format!("unknown command {:?}, try \"help\"", command)),
+        };
+
+        Ok(reply)
+    }
+}
+
+pub fn run(program: &[Instruction], input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    let mut debugger = Debugger::new(program);
+    for line in input.lines() {
+        let line = line?;
+        let mut words = line.split_whitespace();
+        let command = match words.next() {
+            Some(command) => command,
+            None => continue,
+        };
+        if command == "quit" || command == "q" {
+            break;
+        }
+
+        let argument = match words.next().map(|w| w.parse::<i32>()) {
+            Some(Ok(n)) => Some(n),
+            Some(Err(_)) => {
+                writeln!(output, "error: expected a number after {}", command)?;
+                continue;
+            },
+            None => None,
+        };
+
+        match debugger.execute(command, argument) {
+            Ok(reply) => writeln!(output, "{}", reply)?,
+            Err(e) => writeln!(output, "error: {}", e)?,
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::parse_instructions;
+
+    fn session(script: &str) -> String {
+        let (_, program) = parse_instructions(include_str!("input-test.txt")).unwrap();
+        let mut output = vec![];
+        run(&program, Cursor::new(script), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn stops_at_breakpoint() {
+        assert_eq!(session("break 21\ncontinue\nstate\n"), "\
+breakpoint set on X=21
+breakpoint X=21 hit, cycle 19: X=21; X after cycle=21; instruction 10 (addx -1), 0/2 cycles done
+cycle 19: X=21; X after cycle=21; instruction 10 (addx -1), 0/2 cycles done
+");
+    }
+
+    #[test]
+    fn rejects_negative_counts() {
+        assert_eq!(session("instr -1\nstep -5\nstate\n"), "\
+error: instr expects a number that is not negative
+error: step expects a number that is not negative
+not started; X after cycle=1; instruction 0 (addx 15), 0/2 cycles done
+");
+    }
+}
@@ -1,26 +1,117 @@
-use std::fs;
+mod debugger;
+mod ocr;
 
-use nom::{
-    branch::alt,
+use std::{env, fmt, fs, io};
 
+use nom::{
     IResult,
     bytes::complete::tag,
     multi::separated_list1,
-    character::complete::newline,
+    sequence::{pair, preceded},
+    combinator::{map_opt, opt},
+    character::complete::{alpha1, newline},
 };
 
-#[derive(Clone, Copy, Debug)]
-enum Instruction {
-    AddX(i32),
-    NoOp,
+/// Describes one kind of instruction: how it is spelled, how many cycles it
+/// takes, and what it does to the X register once it completes.
+struct Operation {
+    name: &'static str,
+    cycles: u32,
+    takes_operand: bool,
+    execute: fn(register_value: i32, operand: i32) -> i32,
 }
 
+const INSTRUCTION_SET: &[Operation] = &[
+    Operation { name: "noop", cycles: 1, takes_operand: false, execute: |x, _| x },
+    Operation { name: "addx", cycles: 2, takes_operand: true, execute: |x, v| x + v },
+];
+
+const SIGNAL_CYCLES: [i32; 6] = [20, 60, 100, 140, 180, 220];
+
 #[derive(Clone, Copy)]
+struct Instruction {
+    operation: &'static Operation,
+    operand: i32,
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.operation.takes_operand {
+            true => write!(f, "{} {}", self.operation.name, self.operand),
+            false => write!(f, "{}", self.operation.name),
+        }
+    }
+}
+
+impl Instruction {
+    fn new(name: &str, operand: Option<i32>) -> Option<Self> {
+        let operation = INSTRUCTION_SET.iter().find(|op| op.name == name)?;
+        if operation.takes_operand != operand.is_some() {
+            return None;
+        }
+
+        Some(Instruction { operation, operand: operand.unwrap_or(0) })
+    }
+}
+
+/// The value of X *during* `cycle`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 struct MachineState {
     register_value: i32,
     cycle: i32,
 }
 
+/// Steps through a program one cycle at a time, yielding the machine state
+/// during every cycle until the last instruction completes.
+struct Cpu<'a> {
+    program: &'a [Instruction],
+    instruction_pointer: usize,
+    cycles_in_instruction: u32,
+    state: MachineState,
+}
+
+impl<'a> Cpu<'a> {
+    fn new(program: &'a [Instruction]) -> Self {
+        Cpu {
+            program,
+            instruction_pointer: 0,
+            cycles_in_instruction: 0,
+            state: MachineState { register_value: 1, cycle: 0 },
+        }
+    }
+
+    /// The register value after the last completed cycle.
+    fn register_value(&self) -> i32 {
+        self.state.register_value
+    }
+}
+
+impl Iterator for Cpu<'_> {
+    type Item = MachineState;
+
+    fn next(&mut self) -> Option<MachineState> {
+        let instruction = self.program.get(self.instruction_pointer)?;
+        self.state.cycle += 1;
+        let during = self.state;
+
+        self.cycles_in_instruction += 1;
+        if self.cycles_in_instruction == instruction.operation.cycles {
+            self.state.register_value = (instruction.operation.execute)(self.state.register_value, instruction.operand);
+            self.instruction_pointer += 1;
+            self.cycles_in_instruction = 0;
+        }
+
+        Some(during)
+    }
+}
+
+fn signal_strength(program: &[Instruction], cycles_of_interest: &[i32]) -> i32 {
+    Cpu::new(program)
+        .filter(|state| cycles_of_interest.contains(&state.cycle))
+        .map(|state| state.cycle * state.register_value)
+        .sum()
+}
+
 struct Screen {
     num_rows: i32,
     num_cols: i32,
@@ -44,6 +135,9 @@ impl Screen {
     fn draw(&mut self, state: MachineState) {
         let pixel_position = state.cycle - 1;
         let pixel_row = pixel_position / self.num_cols;
+        if pixel_row >= self.num_rows {
+            return;
+        }
         let sprite_position = state.register_value;
 
         let c = match self.sprite_is_visible(sprite_position, pixel_position) {
@@ -53,6 +147,13 @@ impl Screen {
         self.pixels[pixel_row as usize].push(c);
     }
 
+    fn render(&self) -> String {
+        self.pixels.iter()
+            .map(|row| row.iter().collect::<String>())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
     fn sprite_is_visible(&self, sprite_position: i32, pixel_position: i32) -> bool {
         let pixel_row = pixel_position / self.num_cols;
         let normalized_pixel_position = pixel_position - (pixel_row * self.num_cols);
@@ -60,55 +161,65 @@ impl Screen {
     }
 }
 
-fn process_instruction(state: &MachineState, instruction: Instruction) -> MachineState {
-    match instruction {
-        Instruction::AddX(x) => MachineState { register_value: state.register_value + x, cycle: state.cycle + 2 },
-        Instruction::NoOp => MachineState { register_value: state.register_value, cycle: state.cycle + 1 },
-    }
+fn parse_cycles(arg: &str) -> Option<Vec<i32>> {
+    arg.split(',')
+        .map(|c| c.trim().parse().ok())
+        .collect()
 }
 
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let debug = args.iter().any(|a| a == "--debug");
+    let cycles_of_interest = match args.iter().find(|a| *a != "--debug") {
+        Some(arg) => parse_cycles(arg).unwrap_or_else(|| {
+            eprintln!("usage: day10 [--debug] [CYCLE,CYCLE,...]");
+            std::process::exit(2);
+        }),
+        None => SIGNAL_CYCLES.to_vec(),
+    };
+
     let input = fs::read_to_string("./src/input.txt").expect("Should be able to read input file");
     let (_, instructions) = parse_instructions(&input).expect("Should be able to parse instructions from input");
 
-    let mut curr_state = MachineState { register_value: 1, cycle: 1 };
-    let mut cycle_of_interest: Vec<i32> = (1..=240).rev().collect();
-    let mut screen = Screen::new(6, 40);
-    for instruction in instructions {
-        let new_state = process_instruction(&curr_state, instruction);
-
-        while !cycle_of_interest.is_empty() && cycle_of_interest.last().unwrap() < &new_state.cycle {
-            let cycle = cycle_of_interest.pop().unwrap();
-            let state_for_cycle = MachineState { cycle, register_value: curr_state.register_value };
+    if debug {
+        debugger::run(&instructions, io::stdin().lock(), io::stdout()).expect("Should be able to talk to the terminal");
+        return;
+    }
 
-            screen.draw(state_for_cycle);
-        }
+    println!("{}", signal_strength(&instructions, &cycles_of_interest));
 
-        curr_state = new_state;
+    let mut screen = Screen::new(6, 40);
+    let mut cpu = Cpu::new(&instructions);
+    let mut last_cycle = 0;
+    for state in cpu.by_ref() {
+        screen.draw(state);
+        last_cycle = state.cycle;
     }
 
-    for cycle in cycle_of_interest {
-        let state_for_cycle = MachineState { cycle, register_value: curr_state.register_value };
-        screen.draw(state_for_cycle);
+    // X keeps its final value once the program has finished
+    for cycle in last_cycle + 1..=screen.num_rows * screen.num_cols {
+        screen.draw(MachineState { cycle, register_value: cpu.register_value() });
     }
 
-    for row in screen.pixels {
-        println!("{:?}", row);
+    println!("{}", screen.render());
+
+    match ocr::recognise(&screen.pixels) {
+        Ok(text) => println!("{}", text),
+        Err(e) => {
+            eprintln!("Could not read screen: {}", e);
+            std::process::exit(1);
+        }
     }
 }
 
 fn parse_instructions(input: &str) -> IResult<&str, Vec<Instruction>> {
-    let (input, instructions) = separated_list1(newline, alt((parse_addx, parse_noop)))(input)?;
+    let (input, instructions) = separated_list1(newline, parse_instruction)(input)?;
     Ok((input, instructions))
 }
 
-fn parse_addx(input: &str) -> IResult<&str, Instruction> {
-    let (input, _) = tag("addx ")(input)?;
-    let (input, x) = nom::character::complete::i32(input)?;
-    Ok((input, Instruction::AddX(x)))
-}
-
-fn parse_noop(input: &str) -> IResult<&str, Instruction> {
-    let (input, _) = tag("noop")(input)?;
-    Ok((input, Instruction::NoOp))
+fn parse_instruction(input: &str) -> IResult<&str, Instruction> {
+    map_opt(
+        pair(alpha1, opt(preceded(tag(" "), nom::character::complete::i32))),
+        |(name, operand)| Instruction::new(name, operand),
+    )(input)
 }
@@ -0,0 +1,125 @@
+use std::fmt;
+
+pub const GLYPH_WIDTH: usize = 4;
+pub const GLYPH_HEIGHT: usize = 6;
+const GLYPH_SPACING: usize = 1;
+
+/// The letters of the Advent of Code CRT font that are known to appear in
+/// puzzle answers, each drawn as six rows of four pixels.
+const FONT: &[(char, [&str; GLYPH_HEIGHT])] = &[
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum OcrError {
+    WrongHeight(usize),
+    RaggedRow { row: usize, len: usize },
+    UnknownGlyph { index: usize, column: usize, pixels: Vec<String> },
+}
+
+impl fmt::Display for OcrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OcrError::WrongHeight(height) => {
+                write!(f, "expected {} rows of pixels, got {}", GLYPH_HEIGHT, height)
+            },
+            OcrError::RaggedRow { row, len } => {
+                write!(f, "row {} has {} pixels, which differs from row 0", row, len)
+            },
+            OcrError::UnknownGlyph { index, column, pixels } => {
+                write!(f, "unknown glyph {} at column {}: {}", index, column, pixels.join("/"))
+            },
+        }
+    }
+}
+
+/// Reads the letters drawn on a CRT screen. Glyphs are four pixels wide with
+/// a one pixel gap between them; a trailing partial glyph is ignored if it is
+/// entirely dark.
+pub fn recognise(pixels: &[Vec<char>]) -> Result<String, OcrError> {
+    if pixels.len() != GLYPH_HEIGHT {
+        return Err(OcrError::WrongHeight(pixels.len()));
+    }
+    let width = pixels[0].len();
+    if let Some((row, r)) = pixels.iter().enumerate().find(|(_, r)| r.len() != width) {
+        return Err(OcrError::RaggedRow { row, len: r.len() });
+    }
+
+    let mut text = String::new();
+    let mut column = 0;
+    for index in 0.. {
+        if column >= width {
+            break;
+        }
+        let end = (column + GLYPH_WIDTH).min(width);
+        let glyph: Vec<String> = pixels.iter()
+            .map(|row| row[column..end].iter().collect())
+            .collect();
+
+        if end - column < GLYPH_WIDTH && glyph.iter().all(|r| !r.contains('#')) {
+            break;
+        }
+
+        let letter = FONT.iter()
+            .find(|(_, rows)| rows.iter().zip(&glyph).all(|(a, b)| a == b))
+            .map(|(letter, _)| *letter);
+        match letter {
+            Some(letter) => text.push(letter),
+            None => return Err(OcrError::UnknownGlyph { index, column, pixels: glyph }),
+        }
+
+        column += GLYPH_WIDTH + GLYPH_SPACING;
+    }
+
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Lays the glyph rows side by side with a one pixel gap.
+    fn screen(glyphs: &[[&str; GLYPH_HEIGHT]]) -> Vec<Vec<char>> {
+        (0..GLYPH_HEIGHT)
+            .map(|row| glyphs.iter().map(|g| g[row]).collect::<Vec<&str>>().join(".").chars().collect())
+            .collect()
+    }
+
+    fn glyph(letter: char) -> [&'static str; GLYPH_HEIGHT] {
+        FONT.iter().find(|(c, _)| *c == letter).unwrap().1
+    }
+
+    #[test]
+    fn reads_known_glyphs() {
+        assert_eq!(recognise(&screen(&[glyph('H'), glyph('E'), glyph('L'), glyph('P')])), Ok("HELP".to_string()));
+    }
+
+    #[test]
+    fn reports_unknown_glyph() {
+        let unknown = ["#.#.", ".#.#", "#.#.", ".#.#", "#.#.", ".#.#"];
+        assert_eq!(
+            recognise(&screen(&[glyph('O'), glyph('K'), unknown])),
+            Err(OcrError::UnknownGlyph {
+                index: 2,
+                column: 10,
+                pixels: unknown.iter().map(|r| r.to_string()).collect(),
+            }),
+        );
+    }
+}
@@ -0,0 +1,153 @@
+use std::fmt;
+
+use nom::{
+    IResult,
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{self, one_of, space0},
+    combinator::value,
+    error::{Error, ErrorKind},
+    multi::many0,
+    sequence::{delimited, pair},
+    Parser,
+};
+
+use crate::worry::Worry;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+impl Operator {
+    fn precedence(self) -> u8 {
+        match self {
+            Operator::Add | Operator::Subtract => 1,
+            Operator::Multiply | Operator::Divide => 2,
+        }
+    }
+
+    fn symbol(self) -> char {
+        match self {
+            Operator::Add => '+',
+            Operator::Subtract => '-',
+            Operator::Multiply => '*',
+            Operator::Divide => '/',
+        }
+    }
+}
+
+/// The right hand side of a monkey's `new = ...` operation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expr {
+    Old,
+    Literal(u64),
+    Binary(Box<Expr>, Operator, Box<Expr>),
+}
+
+impl Expr {
+    /// Returns `None` when a step goes below zero, divides by zero or does
+    /// not fit in `W`.
+    pub fn eval<W: Worry>(&self, old: &W) -> Option<W> {
+        match self {
+            Expr::Old => Some(old.clone()),
+            Expr::Literal(n) => Some(W::from(*n)),
+            Expr::Binary(left, operator, right) => {
+                let (left, right) = (left.eval(old)?, right.eval(old)?);
+                match operator {
+                    Operator::Add => left.checked_add(&right),
+                    Operator::Subtract => left.checked_sub(&right),
+                    Operator::Multiply => left.checked_mul(&right),
+                    Operator::Divide => left.checked_div(&right),
+                }
+            },
+        }
+    }
+
+    pub fn uses(&self, operator: Operator) -> bool {
+        match self {
+            Expr::Binary(left, op, right) => *op == operator || left.uses(operator) || right.uses(operator),
+            _ => false,
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Binary(_, operator, _) => operator.precedence(),
+            _ => u8::MAX,
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Old => write!(f, "old"),
+            Expr::Literal(n) => write!(f, "{}", n),
+            Expr::Binary(left, operator, right) => {
+                // operators are left associative, so a right operand of equal
+                // precedence still needs brackets
+                let prec = operator.precedence();
+                match left.precedence() < prec {
+                    true => write!(f, "({})", left)?,
+                    false => write!(f, "{}", left)?,
+                }
+                write!(f, " {} ", operator.symbol())?;
+                match right.precedence() <= prec {
+                    true => write!(f, "({})", right),
+                    false => write!(f, "{}", right),
+                }
+            },
+        }
+    }
+}
+
+/// Parses an expression over `old` and unsigned literals with the usual
+/// precedence of `+ - * /` and brackets for nesting. Dividing by a literal
+/// `0` is rejected.
+pub fn parse_expr(input: &str) -> IResult<&str, Expr> {
+    let (input, first) = parse_term(input)?;
+    let (input, rest) = many0(pair(
+        delimited(space0, one_of("+-"), space0),
+        parse_term,
+    ))(input)?;
+
+    Ok((input, fold(first, rest)))
+}
+
+fn parse_term(input: &str) -> IResult<&str, Expr> {
+    let (input, first) = parse_factor(input)?;
+    let (input, rest) = many0(pair(
+        delimited(space0, one_of("*/"), space0),
+        parse_factor,
+    ))(input)?;
+    if rest.iter().any(|(symbol, right)| *symbol == '/' && *right == Expr::Literal(0)) {
+        return Err(nom::Err::Failure(Error::new(input, ErrorKind::Verify)));
+    }
+
+    Ok((input, fold(first, rest)))
+}
+
+fn parse_factor(input: &str) -> IResult<&str, Expr> {
+    alt((
+        value(Expr::Old, tag("old")),
+        complete::u64.map(Expr::Literal),
+        delimited(pair(tag("("), space0), parse_expr, pair(space0, tag(")"))),
+    ))(input)
+}
+
+fn fold(first: Expr, rest: Vec<(char, Expr)>) -> Expr {
+    rest.into_iter().fold(first, |left, (symbol, right)| {
+        let operator = match symbol {
+            '+' => Operator::Add,
+            '-' => Operator::Subtract,
+            '*' => Operator::Multiply,
+            '/' => Operator::Divide,
+            _ => unreachable!("parser only accepts + - * /"),
+        };
+        Expr::Binary(Box::new(left), operator, Box::new(right))
+    })
+}
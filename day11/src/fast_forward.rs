@@ -0,0 +1,76 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::{
+    worry::{Worry, WorryPolicy},
+    Monkey,
+    Simulation,
+};
+
+/// Counts inspections after `num_rounds` rounds without playing every round.
+///
+/// Items never affect each other, so each one is simulated alone. Its state
+/// at the start of a round is just who holds it and its worry level; once
+/// that state repeats, the inspections it causes repeat with it and the rest
+/// of the rounds can be extrapolated. With worry kept modulo the LCM there
+/// are finitely many states, so a repeat is guaranteed.
+pub fn fast_forward<W: Worry>(monkeys: &[Monkey], policy: WorryPolicy, num_rounds: u64) -> Result<Vec<u64>, String> {
+    let empty: Vec<Monkey> = monkeys.iter()
+        .map(|monkey| Monkey { items: VecDeque::new(), ..monkey.clone() })
+        .collect();
+
+    let mut total = vec![0; monkeys.len()];
+    for (holder, monkey) in monkeys.iter().enumerate() {
+        for item in &monkey.items {
+            let mut alone = Simulation::<W>::new(&empty, policy)?;
+            alone.monkeys[holder].items.push_back(W::from(*item));
+
+            let counts = item_inspections(&mut alone, num_rounds)?;
+            for (t, c) in total.iter_mut().zip(counts) {
+                *t += c;
+            }
+        }
+    }
+
+    Ok(total)
+}
+
+fn item_inspections<W: Worry>(simulation: &mut Simulation<W>, num_rounds: u64) -> Result<Vec<u64>, String> {
+    let mut first_seen: HashMap<(usize, W), u64> = HashMap::new();
+    // history[r] is the inspection count per monkey after r rounds
+    let mut history: Vec<Vec<u64>> = vec![simulation.num_inspections()];
+
+    for round in 0..num_rounds {
+        let state = item_state(simulation);
+        if let Some(start) = first_seen.insert(state, round) {
+            return Ok(extrapolate(&history, start, round, num_rounds));
+        }
+
+        simulation.play_round()?;
+        history.push(simulation.num_inspections());
+    }
+
+    Ok(history.pop().unwrap())
+}
+
+fn item_state<W: Worry>(simulation: &Simulation<W>) -> (usize, W) {
+    simulation.monkeys.iter()
+        .enumerate()
+        .find_map(|(idx, monkey)| monkey.items.front().map(|item| (idx, item.clone())))
+        .expect("the simulated item should be held by a monkey")
+}
+
+/// The rounds `start..end` repeat forever, so the count after `num_rounds`
+/// is the count at the matching point of the first cycle plus the gain of
+/// every completed cycle.
+fn extrapolate(history: &[Vec<u64>], start: u64, end: u64, num_rounds: u64) -> Vec<u64> {
+    let length = end - start;
+    let full_cycles = (num_rounds - start) / length;
+    let remainder = (num_rounds - start) % length;
+
+    let at_start = &history[start as usize];
+    let at_end = &history[end as usize];
+    let at_remainder = &history[(start + remainder) as usize];
+    (0..at_start.len())
+        .map(|m| at_remainder[m] + full_cycles * (at_end[m] - at_start[m]))
+        .collect()
+}
@@ -1,49 +1,125 @@
-use std::{collections::VecDeque, fs};
+mod expr;
+mod fast_forward;
+mod stats;
+mod worry;
+
+use std::{collections::VecDeque, env, fmt, fs};
 
 use nom::{
     IResult,
     bytes::complete::tag,
-    multi::separated_list1,
-    character::complete::{self, newline, anychar, alphanumeric1}, branch::alt,
+    combinator::all_consuming,
+    multi::{separated_list0, separated_list1},
+    character::complete::{self, newline},
 };
+use num_bigint::BigUint;
+
+use expr::{parse_expr, Expr, Operator};
+use stats::{snapshots_to_csv, ItemTracker, RoundSnapshot};
+use worry::{lcm, Worry, WorryPolicy};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Test {
+    divisor: u64,
+    if_true: u32,
+    if_false: u32,
+}
+
+impl Test {
+    fn target<W: Worry>(&self, worry: &W) -> u32 {
+        match worry.is_multiple_of_u64(self.divisor) {
+            true => self.if_true,
+            false => self.if_false,
+        }
+    }
+}
 
-struct Monkey {
-    items: VecDeque<u64>,
-    operation: Box<dyn Fn(u64) -> u64>,
-    test: Box<dyn Fn(u64) -> bool>,
-    test_divisor: u64,
-    yes_monkey: u32,
-    no_monkey: u32,
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Monkey<W = u64> {
+    id: u32,
+    items: VecDeque<W>,
+    operation: Expr,
+    test: Test,
     num_inspections: u64,
 }
 
-impl Monkey {
-    fn inspect_one_item(&mut self, modulo: u64) -> (u64, u32) {
+impl<W: Worry> Monkey<W> {
+    fn inspect_one_item(&mut self, policy: WorryPolicy, lcm: u64) -> Result<(W, u32), String> {
         self.num_inspections += 1;
         let item = self.items.pop_front().unwrap();
-        let new_item = (self.operation)(item);
-        if (self.test)(new_item) {
-            (new_item % modulo, self.yes_monkey)
-        } else {
-            (new_item % modulo, self.no_monkey)
+        let new_item = self.operation.eval(&item).ok_or_else(|| {
+            format!("monkey {}: new = {} is out of range for old = {}", self.id, self.operation, item)
+        })?;
+        let new_item = policy.apply(new_item, lcm);
+        let target = self.test.target(&new_item);
+        Ok((new_item, target))
+    }
+}
+
+impl Monkey {
+    fn with_worry<W: Worry>(&self) -> Monkey<W> {
+        Monkey {
+            id: self.id,
+            items: self.items.iter().map(|i| W::from(*i)).collect(),
+            operation: self.operation.clone(),
+            test: self.test.clone(),
+            num_inspections: self.num_inspections,
         }
     }
 }
 
-fn main() {
-    let input = fs::read_to_string("./src/input.txt").expect("Should be able to read input file");
-    let (_, mut monkeys) = parse_monkeys(&input).expect("Should be able to parse input");
-    let num_rounds = 10000;
+impl<W: Worry> fmt::Display for Monkey<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let items: Vec<String> = self.items.iter().map(|i| i.to_string()).collect();
+        writeln!(f, "Monkey {}:", self.id)?;
+        writeln!(f, "  Starting items: {}", items.join(", "))?;
+        writeln!(f, "  Operation: new = {}", self.operation)?;
+        writeln!(f, "  Test: divisible by {}", self.test.divisor)?;
+        writeln!(f, "    If true: throw to monkey {}", self.test.if_true)?;
+        writeln!(f, "    If false: throw to monkey {}", self.test.if_false)
+    }
+}
+
+struct Simulation<W> {
+    monkeys: Vec<Monkey<W>>,
+    policy: WorryPolicy,
+    lcm: u64,
+    round: u64,
+    snapshots: Option<Vec<RoundSnapshot<W>>>,
+    tracker: Option<ItemTracker<W>>,
+}
+
+impl<W: Worry> Simulation<W> {
+    fn new(monkeys: &[Monkey], policy: WorryPolicy) -> Result<Self, String> {
+        let monkeys: Vec<Monkey<W>> = monkeys.iter().map(|m| m.with_worry()).collect();
+        let lcm = match lcm(monkeys.iter().map(|monkey| monkey.test.divisor)) {
+            Some(lcm) => lcm,
+            None if policy == WorryPolicy::ModuloLcm => {
+                return Err("the LCM of the monkeys' divisors does not fit in 64 bits".to_string());
+            },
+            // only the lcm policy reduces by it
+            None => 0,
+        };
+        Ok(Simulation { monkeys, policy, lcm, round: 0, snapshots: None, tracker: None })
+    }
+
+    fn record_snapshots(&mut self) {
+        self.snapshots = Some(vec![RoundSnapshot::take(self.round, &self.monkeys)]);
+    }
 
-    let modulo = monkeys.iter()
-        .map(|monkey| monkey.test_divisor)
-        .product::<u64>();
+    fn track_items(&mut self) {
+        self.tracker = Some(ItemTracker::new(&self.monkeys));
+    }
 
-    for _ in 0..num_rounds {
+    fn play_round(&mut self) -> Result<(), String> {
+        let monkeys = &mut self.monkeys;
         for monkey_index in 0..monkeys.len() {
             for _ in 0..monkeys[monkey_index].items.len() {
                 let monkey = monkeys.get_mut(monkey_index).unwrap();
-                let (new_item, monkey_to_send_to) = monkey.inspect_one_item(modulo);
+                let (new_item, monkey_to_send_to) = monkey.inspect_one_item(self.policy, self.lcm)?;
+                if let Some(tracker) = &mut self.tracker {
+                    tracker.record_throw(monkey_index, monkey_to_send_to, &new_item);
+                }
                 monkeys
                     .get_mut(monkey_to_send_to as usize)
                     .unwrap().
@@ -51,17 +127,186 @@ fn main() {
                     push_back(new_item);
             }
         }
+
+        self.round += 1;
+        if let Some(snapshots) = &mut self.snapshots {
+            snapshots.push(RoundSnapshot::take(self.round, monkeys));
+        }
+
+        Ok(())
+    }
+
+    fn num_inspections(&self) -> Vec<u64> {
+        self.monkeys.iter().map(|monkey| monkey.num_inspections).collect()
     }
+}
 
-    monkeys.sort_by_key(|monkey| monkey.num_inspections);
-    let result = monkeys
+fn monkey_business(num_inspections: &[u64]) -> u128 {
+    let mut num_inspections = num_inspections.to_vec();
+    num_inspections.sort();
+    num_inspections
         .iter()
         .rev()
-        .map(|monkey| monkey.num_inspections)
         .take(2)
-        .product::<u64>();
+        .map(|n| *n as u128)
+        .product::<u128>()
+}
+
+fn simulate<W: Worry>(monkeys: &[Monkey], options: &Options) -> Result<u128, String> {
+    if options.fast_forward {
+        return Ok(monkey_business(&fast_forward::fast_forward::<W>(monkeys, options.policy, options.num_rounds)?));
+    }
+
+    let mut simulation = Simulation::<W>::new(monkeys, options.policy)?;
+    if options.csv_path.is_some() {
+        simulation.record_snapshots();
+    }
+    if options.trace_items {
+        simulation.track_items();
+    }
+
+    for _ in 0..options.num_rounds {
+        simulation.play_round()?;
+    }
+
+    if let (Some(path), Some(snapshots)) = (&options.csv_path, &simulation.snapshots) {
+        fs::write(path, snapshots_to_csv(snapshots)).expect("Should be able to write csv file");
+    }
+    if let Some(tracker) = &simulation.tracker {
+        for route in tracker.routes(monkeys.len()) {
+            println!("{}", route.describe());
+        }
+    }
+
+    Ok(monkey_business(&simulation.num_inspections()))
+}
+
+fn run(monkeys: &[Monkey], options: &Options) -> Result<u128, String> {
+    if options.policy == WorryPolicy::ModuloLcm {
+        if let Some(monkey) = monkeys.iter().find(|m| m.operation.uses(Operator::Divide) || m.operation.uses(Operator::Subtract)) {
+            return Err(format!(
+                "monkey {} divides or subtracts (new = {}), which the lcm policy cannot keep test results for",
+                monkey.id, monkey.operation,
+            ));
+        }
+    }
+
+    match options.policy {
+        WorryPolicy::Unbounded => simulate::<BigUint>(monkeys, options),
+        // dividing keeps levels down but does not bound them, so an `old * old`
+        // can still outgrow a u64 given enough rounds
+        WorryPolicy::DivideBy(k) => simulate::<u64>(monkeys, options)
+            .map_err(|e| format!("{} (worry levels outgrew 64 bits under div:{})", e, k)),
+        WorryPolicy::ModuloLcm => simulate::<u64>(monkeys, options),
+    }
+}
+
+struct Options {
+    policy: WorryPolicy,
+    num_rounds: u64,
+    csv_path: Option<String>,
+    trace_items: bool,
+    fast_forward: bool,
+}
+
+impl Options {
+    fn new(policy: WorryPolicy, num_rounds: u64) -> Self {
+        Options { policy, num_rounds, csv_path: None, trace_items: false, fast_forward: false }
+    }
+}
+
+/// Returns `None` when no arguments are given, in which case both parts are
+/// solved with their default settings.
+fn parse_options(args: &[String]) -> Result<Option<Options>, String> {
+    if args.is_empty() {
+        return Ok(None);
+    }
+
+    let mut policy = None;
+    let mut num_rounds = None;
+    let mut csv_path = None;
+    let mut trace_items = false;
+    let mut fast_forward = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} expects a value", arg));
+        match arg.as_str() {
+            "--policy" => {
+                let v = value()?;
+                policy = Some(WorryPolicy::parse(v).ok_or_else(|| format!("invalid policy {:?}", v))?);
+            },
+            "--rounds" => {
+                let v = value()?;
+                num_rounds = Some(v.parse().map_err(|_| format!("invalid round count {:?}", v))?);
+            },
+            "--csv" => csv_path = Some(value()?.clone()),
+            "--trace-items" => trace_items = true,
+            "--fast-forward" => fast_forward = true,
+            _ => return Err(format!("unknown argument {:?}", arg)),
+        }
+    }
+
+    let policy = policy.unwrap_or(WorryPolicy::ModuloLcm);
+    let num_rounds = num_rounds.unwrap_or(match policy {
+        WorryPolicy::DivideBy(_) => 20,
+        _ => 10000,
+    });
+    if fast_forward && policy != WorryPolicy::ModuloLcm {
+        return Err("--fast-forward needs the lcm policy, the only one that keeps worry levels bounded".to_string());
+    }
+    if fast_forward && (csv_path.is_some() || trace_items) {
+        return Err("--fast-forward skips rounds, so it cannot be combined with --csv or --trace-items".to_string());
+    }
+
+    Ok(Some(Options { policy, num_rounds, csv_path, trace_items, fast_forward }))
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = parse_options(&args).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        eprintln!("usage: day11 [--policy div:K|lcm|none] [--rounds N] [--csv PATH] [--trace-items] [--fast-forward]");
+        std::process::exit(2);
+    });
+
+    let input = fs::read_to_string("./src/input.txt").expect("Should be able to read input file");
+    let (_, monkeys) = all_consuming(parse_monkeys)(&input).expect("Should be able to parse input");
+    if let Err(e) = check_monkeys(&monkeys) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+
+    let result = match options {
+        Some(options) => run(&monkeys, &options).map(|answer| println!("{}", answer)),
+        None => run(&monkeys, &Options::new(WorryPolicy::DivideBy(3), 20))
+            .map(|answer| println!("Part 1: {}", answer))
+            .and_then(|_| run(&monkeys, &Options::new(WorryPolicy::ModuloLcm, 10000)))
+            .map(|answer| println!("Part 2: {}", answer)),
+    };
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
 
-    println!("{}", result);
+/// Every monkey must test for a nonzero divisor and throw to a monkey that
+/// exists.
+fn check_monkeys(monkeys: &[Monkey]) -> Result<(), String> {
+    for monkey in monkeys {
+        if monkey.test.divisor == 0 {
+            return Err(format!("monkey {} tests for divisibility by 0", monkey.id));
+        }
+        for target in [monkey.test.if_true, monkey.test.if_false] {
+            if target as usize >= monkeys.len() {
+                return Err(format!(
+                    "monkey {} throws to monkey {}, but there are only {} monkeys",
+                    monkey.id, target, monkeys.len(),
+                ));
+            }
+        }
+    }
+
+    Ok(())
 }
 
 fn parse_monkeys(input: &str) -> IResult<&str, Vec<Monkey>> {
@@ -70,9 +315,8 @@ fn parse_monkeys(input: &str) -> IResult<&str, Vec<Monkey>> {
 }
 
 fn parse_monkey(input: &str) -> IResult<&str, Monkey> {
-    // Parse out and ignore the first line of each monkey
     let (input, _) = tag("Monkey ")(input)?;
-    let (input, _) = complete::u32(input)?;
+    let (input, id) = complete::u32(input)?;
     let (input, _) = tag(":")(input)?;
     let (input, _) = newline(input)?;
 
@@ -80,22 +324,16 @@ fn parse_monkey(input: &str) -> IResult<&str, Monkey> {
     let (input, _) = newline(input)?;
     let (input, operation) = parse_operation(input)?;
     let (input, _) = newline(input)?;
-    let (input, (test, test_divisor)) = parse_test(input)?;
-    let (input, _) = newline(input)?;
-    let (input, yes_monkey) = parse_target_monkey(input)?;
-    let (input, _) = newline(input)?;
-    let (input, no_monkey) = parse_target_monkey(input)?;
+    let (input, test) = parse_test(input)?;
     let (input, _) = newline(input)?;
 
     Ok((
         input,
         Monkey {
+            id,
             items: VecDeque::from(starting_items),
             operation,
             test,
-            test_divisor,
-            yes_monkey,
-            no_monkey,
             num_inspections: 0,
         }
     ))
@@ -103,44 +341,23 @@ fn parse_monkey(input: &str) -> IResult<&str, Monkey> {
 
 fn parse_starting_items(input: &str) -> IResult<&str, Vec<u64>> {
     let (input, _) = tag("  Starting items: ")(input)?;
-    let (input, items) = separated_list1(tag(", "), complete::u64)(input)?;
+    let (input, items) = separated_list0(tag(", "), complete::u64)(input)?;
     Ok((input, items))
 }
 
-fn parse_operation(input: &str) -> IResult<&str, Box<dyn Fn(u64) -> u64>> {
-    let (input, _) = tag("  Operation: new = old ")(input)?;
-    let (input, operator) = anychar(input)?;
-    let (input, _) = tag(" ")(input)?;
-    let (input, operand) = alphanumeric1(input)?;
-    let numeric_operand = match operand.parse::<u64>() {
-        Ok(n) => Some(n),
-        Err(_) => None,
-    };
-
-    if operator == '*' {
-        if let Some(n) = numeric_operand {
-            Ok((input, Box::new(move |x| x * n)))
-        } else {
-            Ok((input, Box::new(|x| x * x)))
-        }
-    } else {
-        if let Some(n) = numeric_operand {
-            Ok((input, Box::new(move |x| x + n)))
-        } else {
-            Ok((input, Box::new(|x| x + x)))
-        }
-    }
+fn parse_operation(input: &str) -> IResult<&str, Expr> {
+    let (input, _) = tag("  Operation: new = ")(input)?;
+    parse_expr(input)
 }
 
-fn parse_test(input: &str) -> IResult<&str, (Box<dyn Fn(u64) -> bool>, u64)> {
+fn parse_test(input: &str) -> IResult<&str, Test> {
     let (input, _) = tag("  Test: divisible by ")(input)?;
-    let (input, val) = complete::u64(input)?;
-    Ok((input, (Box::new(move |x| x % val == 0), val)))
-}
-
-fn parse_target_monkey(input: &str) -> IResult<&str, u32> {
-    let (input, _) = alt((tag("    If true: throw to monkey "), tag("    If false: throw to monkey ")))(input)?;
-    let (input, val) = complete::u32(input)?;
-    Ok((input, val))
+    let (input, divisor) = complete::u64(input)?;
+    let (input, _) = newline(input)?;
+    let (input, _) = tag("    If true: throw to monkey ")(input)?;
+    let (input, if_true) = complete::u32(input)?;
+    let (input, _) = newline(input)?;
+    let (input, _) = tag("    If false: throw to monkey ")(input)?;
+    let (input, if_false) = complete::u32(input)?;
+    Ok((input, Test { divisor, if_true, if_false }))
 }
-
@@ -0,0 +1,142 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::{worry::Worry, Monkey};
+
+pub struct MonkeySnapshot<W> {
+    pub items: Vec<W>,
+    pub num_inspections: u64,
+}
+
+/// The state of every monkey at the end of `round`. Round 0 is the starting
+/// state.
+pub struct RoundSnapshot<W> {
+    pub round: u64,
+    pub monkeys: Vec<MonkeySnapshot<W>>,
+}
+
+impl<W: Worry> RoundSnapshot<W> {
+    pub fn take(round: u64, monkeys: &[Monkey<W>]) -> Self {
+        let monkeys = monkeys.iter()
+            .map(|monkey| MonkeySnapshot {
+                items: monkey.items.iter().cloned().collect(),
+                num_inspections: monkey.num_inspections,
+            })
+            .collect();
+        RoundSnapshot { round, monkeys }
+    }
+}
+
+/// One row per monkey per round. Items are separated by spaces so the column
+/// needs no quoting.
+pub fn snapshots_to_csv<W: Worry>(snapshots: &[RoundSnapshot<W>]) -> String {
+    let mut csv = String::from("round,monkey,num_inspections,items\n");
+    for snapshot in snapshots {
+        for (monkey, state) in snapshot.monkeys.iter().enumerate() {
+            let items: Vec<String> = state.items.iter().map(|i| i.to_string()).collect();
+            csv.push_str(&format!("{},{},{},{}\n", snapshot.round, monkey, state.num_inspections, items.join(" ")));
+        }
+    }
+
+    csv
+}
+
+/// Follows every item as it is thrown between monkeys. Items are numbered in
+/// the order they appear in the input.
+pub struct ItemTracker<W> {
+    held_items: Vec<VecDeque<usize>>,
+    paths: Vec<Vec<(u32, W)>>,
+}
+
+impl<W: Worry> ItemTracker<W> {
+    pub fn new(monkeys: &[Monkey<W>]) -> Self {
+        let mut held_items = vec![];
+        let mut paths = vec![];
+        for (monkey_index, monkey) in monkeys.iter().enumerate() {
+            let mut held = VecDeque::new();
+            for item in &monkey.items {
+                held.push_back(paths.len());
+                paths.push(vec![(monkey_index as u32, item.clone())]);
+            }
+            held_items.push(held);
+        }
+
+        ItemTracker { held_items, paths }
+    }
+
+    /// Records that the item at the front of `from`'s queue was thrown to `to`
+    /// with worry level `worry`.
+    pub fn record_throw(&mut self, from: usize, to: u32, worry: &W) {
+        let id = self.held_items[from].pop_front().expect("tracked queue should match the monkey's items");
+        self.held_items[to as usize].push_back(id);
+        self.paths[id].push((to, worry.clone()));
+    }
+
+    pub fn routes(&self, num_monkeys: usize) -> Vec<ItemRoute> {
+        self.paths.iter()
+            .enumerate()
+            .map(|(id, path)| ItemRoute::new(id, path, num_monkeys))
+            .collect()
+    }
+}
+
+/// A repeated stretch of an item's route. Once the item is held by the same
+/// monkey with the same worry level twice it must repeat forever.
+pub struct RouteCycle {
+    pub start: usize,
+    pub length: usize,
+}
+
+pub struct ItemRoute {
+    pub id: usize,
+    pub monkeys: Vec<u32>,
+    pub inspections_per_monkey: Vec<u64>,
+    pub cycle: Option<RouteCycle>,
+}
+
+impl ItemRoute {
+    fn new<W: Worry>(id: usize, path: &[(u32, W)], num_monkeys: usize) -> Self {
+        // every holder but the last has inspected and thrown the item
+        let mut inspections_per_monkey = vec![0; num_monkeys];
+        for (monkey, _) in &path[..path.len() - 1] {
+            inspections_per_monkey[*monkey as usize] += 1;
+        }
+
+        let mut first_seen: HashMap<&(u32, W), usize> = HashMap::new();
+        let mut cycle = None;
+        for (idx, state) in path.iter().enumerate() {
+            if let Some(start) = first_seen.insert(state, idx) {
+                cycle = Some(RouteCycle { start, length: idx - start });
+                break;
+            }
+        }
+
+        ItemRoute {
+            id,
+            monkeys: path.iter().map(|(monkey, _)| *monkey).collect(),
+            inspections_per_monkey,
+            cycle,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let inspections: u64 = self.inspections_per_monkey.iter().sum();
+        let mut description = format!(
+            "item {} (starts at monkey {}): {} inspections {:?}",
+            self.id, self.monkeys[0], inspections, self.inspections_per_monkey,
+        );
+        match &self.cycle {
+            Some(cycle) => {
+                let cycle_monkeys = &self.monkeys[cycle.start..cycle.start + cycle.length];
+                let shown: Vec<String> = cycle_monkeys.iter().take(12).map(|m| m.to_string()).collect();
+                let ellipsis = if cycle.length > shown.len() { " -> ..." } else { "" };
+                description.push_str(&format!(
+                    ", cycles after {} throws every {} throws via {}{}",
+                    cycle.start, cycle.length, shown.join(" -> "), ellipsis,
+                ));
+            },
+            None => description.push_str(", no cycle found"),
+        }
+
+        description
+    }
+}
@@ -0,0 +1,91 @@
+use std::{
+    fmt,
+    hash::Hash,
+    ops::{Div, Rem},
+};
+
+use num_traits::{CheckedAdd, CheckedDiv, CheckedMul, CheckedSub};
+
+/// A number that can hold an item's worry level. Implemented for `u64` when
+/// the level is kept small and for `BigUint` when it is left to grow.
+pub trait Worry:
+    Clone
+    + Eq
+    + Hash
+    + fmt::Display
+    + From<u64>
+    + CheckedAdd
+    + CheckedSub
+    + CheckedMul
+    + CheckedDiv
+    + Div<Output = Self>
+    + Rem<Output = Self>
+{
+    fn is_multiple_of_u64(&self, divisor: u64) -> bool {
+        self.clone() % Self::from(divisor) == Self::from(0)
+    }
+}
+
+impl<T> Worry for T where
+    T: Clone
+        + Eq
+        + Hash
+        + fmt::Display
+        + From<u64>
+        + CheckedAdd
+        + CheckedSub
+        + CheckedMul
+        + CheckedDiv
+        + Div<Output = T>
+        + Rem<Output = T>
+{}
+
+/// How worry levels are kept in check after each inspection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WorryPolicy {
+    /// Relief divides the level, as in part 1.
+    DivideBy(u64),
+    /// The level is reduced modulo the LCM of every monkey's divisor. This
+    /// leaves every test result unchanged only while operations add and
+    /// multiply: dividing or subtracting a reduced level gives a different
+    /// remainder than doing so to the real one.
+    ModuloLcm,
+    /// The level is left alone and must be held in a big integer.
+    Unbounded,
+}
+
+impl WorryPolicy {
+    pub fn apply<W: Worry>(&self, worry: W, lcm: u64) -> W {
+        match self {
+            WorryPolicy::DivideBy(k) => worry / W::from(*k),
+            WorryPolicy::ModuloLcm => worry % W::from(lcm),
+            WorryPolicy::Unbounded => worry,
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<WorryPolicy> {
+        match s {
+            "lcm" => Some(WorryPolicy::ModuloLcm),
+            "none" => Some(WorryPolicy::Unbounded),
+            _ => {
+                let k = s.strip_prefix("div:")?.parse().ok()?;
+                match k {
+                    0 => None,
+                    k => Some(WorryPolicy::DivideBy(k)),
+                }
+            },
+        }
+    }
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    match b {
+        0 => a,
+        b => gcd(b, a % b),
+    }
+}
+
+/// Returns `None` when the LCM does not fit in a `u64`.
+pub fn lcm(mut values: impl Iterator<Item = u64>) -> Option<u64> {
+    values.try_fold(1u64, |acc, v| (acc / gcd(acc, v)).checked_mul(v))
+}
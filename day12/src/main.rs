@@ -1,97 +1,187 @@
-use std::fs;
+mod render;
 
-fn main() {
-    let input = fs::read_to_string("./src/input.txt").expect("Should be able to read input file");
-
-    let elevation_map = parse_grid(&input);
-    let start_coordinates = find_possible_starts(&elevation_map);
-    let end_coordinate = find_end(&elevation_map).expect("Input should contain an ending position");
+use std::{collections::VecDeque, env, fs};
 
-    let result = start_coordinates
-        .iter()
-        .filter_map(|start_coordinate| bfs(elevation_map.clone(), *start_coordinate, end_coordinate))
-        .min()
-        .expect("Should have at least one valid starting position");
+/// (row, column)
+type Coordinate = (usize, usize);
 
-    println!("{}", result);
+struct Heightmap {
+    cells: Vec<Vec<char>>,
+    start: Coordinate,
+    end: Coordinate,
 }
 
-fn bfs(mut elevation_map: Vec<Vec<char>>, start: (i32, i32), end: (i32, i32)) -> Option<i32> {
-    let visited_marker = '#';
-    let directions: Vec<(i32, i32)> = vec![(0, 1), (0, -1), (1, 0), (-1, 0)];
-    let mut frontier: Vec<(i32, i32)> = vec![start];
-    let mut num_steps = 0;
-    while !frontier.is_empty() {
-        let mut arrived = false;
-        let mut new_frontier: Vec<(i32, i32)> = vec![];
-        for (i, j) in frontier {
-            let curr_elevation = match elevation_map[i as usize][j as usize] {
-                'S' => 'a',
-                c => c
-            };
-
-            if curr_elevation == visited_marker {
-                continue;
-            } 
+impl Heightmap {
+    fn num_rows(&self) -> usize {
+        self.cells.len()
+    }
+
+    fn num_cols(&self) -> usize {
+        self.cells[0].len()
+    }
+
+    fn elevation(&self, (i, j): Coordinate) -> u8 {
+        elevation(self.cells[i][j])
+    }
 
-            elevation_map[i as usize][j as usize] = visited_marker;
-            if (i, j) == end {
-                arrived = true;
-                break;
+    fn neighbours(&self, (i, j): Coordinate) -> impl Iterator<Item = Coordinate> + '_ {
+        let directions: [(isize, isize); 4] = [(0, 1), (0, -1), (1, 0), (-1, 0)];
+        directions.into_iter().filter_map(move |(di, dj)| {
+            let new_i = i.checked_add_signed(di)?;
+            let new_j = j.checked_add_signed(dj)?;
+            match new_i < self.num_rows() && new_j < self.num_cols() {
+                true => Some((new_i, new_j)),
+                false => None,
             }
+        })
+    }
+}
 
-            for (di, dj) in directions.iter() {
-                let new_i = i + di;
-                let new_j = j + dj;
+/// `S` sits at elevation `a` and `E` at elevation `z`.
+fn elevation(c: char) -> u8 {
+    match c {
+        'S' => b'a',
+        'E' => b'z',
+        c => c as u8,
+    }
+}
 
-                if new_i < 0 || new_i >= elevation_map.len() as i32 || new_j < 0 || new_j >= elevation_map[0].len() as i32 {
-                    continue;
-                }
+/// Shortest distances from every cell to the end, along with the next step
+/// to take from each cell.
+struct DistanceMap {
+    distances: Vec<Vec<Option<u32>>>,
+    next_step: Vec<Vec<Option<Coordinate>>>,
+}
 
-                let neighbour_elevation = elevation_map[new_i as usize][new_j as usize];
-                if (neighbour_elevation as i32) - (curr_elevation as i32) <= 1 {
-                    new_frontier.push((new_i, new_j));
-                }
-            }
+impl DistanceMap {
+    fn distance(&self, (i, j): Coordinate) -> Option<u32> {
+        self.distances[i][j]
+    }
+
+    fn path_from(&self, start: Coordinate) -> Option<Vec<Coordinate>> {
+        self.distance(start)?;
+        let mut path = vec![start];
+        let mut curr = start;
+        while let Some(next) = self.next_step[curr.0][curr.1] {
+            path.push(next);
+            curr = next;
         }
+        Some(path)
+    }
+}
+
+/// Searches backwards from the end, so a single pass gives the distance to
+/// the end from every cell. Moving backwards from `u` to `v` is allowed when
+/// the forward move from `v` to `u` climbs at most one step.
+fn reverse_bfs(heightmap: &Heightmap) -> DistanceMap {
+    let mut distances = vec![vec![None; heightmap.num_cols()]; heightmap.num_rows()];
+    let mut next_step = vec![vec![None; heightmap.num_cols()]; heightmap.num_rows()];
+    let mut frontier: VecDeque<Coordinate> = VecDeque::from([heightmap.end]);
+    distances[heightmap.end.0][heightmap.end.1] = Some(0);
+
+    while let Some(curr) = frontier.pop_front() {
+        let curr_distance = distances[curr.0][curr.1].unwrap();
+        let curr_elevation = heightmap.elevation(curr) as i32;
+        for (i, j) in heightmap.neighbours(curr) {
+            if distances[i][j].is_some() {
+                continue;
+            }
 
-        if arrived {
-            return Some(num_steps);
-        } else {
-            num_steps += 1;
-            frontier = new_frontier;
+            if curr_elevation - heightmap.elevation((i, j)) as i32 <= 1 {
+                distances[i][j] = Some(curr_distance + 1);
+                next_step[i][j] = Some(curr);
+                frontier.push_back((i, j));
+            }
         }
     }
 
-    None 
+    DistanceMap { distances, next_step }
 }
 
-fn find_end(elevation_map: &Vec<Vec<char>>) -> Option<(i32, i32)> {
-    for i in 0..elevation_map.len() {
-        for j in 0..elevation_map[0].len() {
-            if elevation_map[i][j] == 'E' {
-                return Some((i as i32, j as i32))
-            }
+fn closest_lowest_point(heightmap: &Heightmap, distance_map: &DistanceMap) -> Option<Coordinate> {
+    (0..heightmap.num_rows())
+        .flat_map(|i| (0..heightmap.num_cols()).map(move |j| (i, j)))
+        .filter(|coordinate| heightmap.elevation(*coordinate) == b'a')
+        .filter(|coordinate| distance_map.distance(*coordinate).is_some())
+        .min_by_key(|coordinate| distance_map.distance(*coordinate))
+}
+
+struct Options {
+    show_path: bool,
+    show_route: bool,
+    show_heatmap: bool,
+    ppm_path: Option<String>,
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options { show_path: false, show_route: false, show_heatmap: false, ppm_path: None };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--path" => options.show_path = true,
+            "--route" => options.show_route = true,
+            "--heatmap" => options.show_heatmap = true,
+            "--ppm" => options.ppm_path = Some(args.next().ok_or("--ppm expects a value")?.clone()),
+            _ => return Err(format!("unknown argument {:?}", arg)),
         }
     }
-    None
+
+    Ok(options)
 }
 
-fn find_possible_starts(elevation_map: &Vec<Vec<char>>) -> Vec<(i32, i32)> {
-    let mut starting_coords = vec![];
-    for i in 0..elevation_map.len() {
-        for j in 0..elevation_map[0].len() {
-            if elevation_map[i][j] == 'S' || elevation_map[i][j] == 'a' {
-                starting_coords.push((i as i32, j as i32));
-            }
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = parse_options(&args).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        eprintln!("usage: day12 [--path] [--route] [--heatmap] [--ppm PATH]");
+        std::process::exit(2);
+    });
+
+    let input = fs::read_to_string("./src/input.txt").expect("Should be able to read input file");
+    let heightmap = parse_heightmap(&input).expect("Input should contain a start and an end position");
+
+    let distance_map = reverse_bfs(&heightmap);
+    let routes = [
+        ("Part 1", Some(heightmap.start)),
+        ("Part 2", closest_lowest_point(&heightmap, &distance_map)),
+    ];
+
+    for (part, start) in routes {
+        match start.and_then(|start| distance_map.path_from(start)) {
+            Some(path) => {
+                println!("{}: {}", part, path.len() - 1);
+                if options.show_path {
+                    println!("{:?}", path);
+                }
+                if options.show_route {
+                    println!("{}", render::render_route(&heightmap, &path));
+                }
+            },
+            None => println!("{}: no route to the summit", part),
         }
     }
-    starting_coords
+
+    if options.show_heatmap {
+        print!("{}", render::render_heatmap_ansi(&heightmap, &distance_map));
+    }
+    if let Some(path) = &options.ppm_path {
+        fs::write(path, render::render_heatmap_ppm(&heightmap, &distance_map, 8)).expect("Should be able to write ppm file");
+    }
 }
 
-fn parse_grid(input: &str) -> Vec<Vec<char>> {
-    input
+fn find(cells: &[Vec<char>], target: char) -> Option<Coordinate> {
+    cells.iter()
+        .enumerate()
+        .find_map(|(i, row)| row.iter().position(|c| *c == target).map(|j| (i, j)))
+}
+
+fn parse_heightmap(input: &str) -> Option<Heightmap> {
+    let cells: Vec<Vec<char>> = input
         .lines()
         .map(|line| line.chars().collect())
-        .collect()
+        .collect();
+    let start = find(&cells, 'S')?;
+    let end = find(&cells, 'E')?;
+
+    Some(Heightmap { cells, start, end })
 }
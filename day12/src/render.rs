@@ -0,0 +1,83 @@
+use crate::{Coordinate, DistanceMap, Heightmap};
+
+const UNREACHABLE: (u8, u8, u8) = (40, 40, 40);
+
+/// Draws a route the way the puzzle does: every step is marked with the
+/// direction taken from that cell, the end is `E` and everything else is `.`.
+pub fn render_route(heightmap: &Heightmap, path: &[Coordinate]) -> String {
+    let mut grid = vec![vec!['.'; heightmap.num_cols()]; heightmap.num_rows()];
+    for step in path.windows(2) {
+        let ((i, j), (next_i, next_j)) = (step[0], step[1]);
+        grid[i][j] = match (next_i as isize - i as isize, next_j as isize - j as isize) {
+            (0, 1) => '>',
+            (0, -1) => '<',
+            (1, 0) => 'v',
+            (-1, 0) => '^',
+            _ => unreachable!("consecutive cells on a route should be adjacent"),
+        };
+    }
+    grid[heightmap.end.0][heightmap.end.1] = 'E';
+
+    to_string(&grid)
+}
+
+fn to_string(grid: &[Vec<char>]) -> String {
+    grid.iter()
+        .map(|row| row.iter().collect::<String>() + "\n")
+        .collect()
+}
+
+/// Blends from red next to the summit to blue for the cells furthest away.
+/// Cells that cannot reach the summit at all are dark grey.
+fn colour(distance: Option<u32>, max_distance: u32) -> (u8, u8, u8) {
+    match distance {
+        Some(d) => {
+            let t = d as f64 / max_distance.max(1) as f64;
+            ((255.0 * (1.0 - t)) as u8, 64, (255.0 * t) as u8)
+        },
+        None => UNREACHABLE,
+    }
+}
+
+fn max_distance(distance_map: &DistanceMap) -> u32 {
+    distance_map.distances.iter()
+        .flatten()
+        .flatten()
+        .copied()
+        .max()
+        .unwrap_or(0)
+}
+
+/// The heightmap with each cell's background coloured by its distance from
+/// the summit, using 24-bit ANSI escape codes.
+pub fn render_heatmap_ansi(heightmap: &Heightmap, distance_map: &DistanceMap) -> String {
+    let max_distance = max_distance(distance_map);
+    let mut out = String::new();
+    for (i, row) in heightmap.cells.iter().enumerate() {
+        for (j, c) in row.iter().enumerate() {
+            let (r, g, b) = colour(distance_map.distance((i, j)), max_distance);
+            out.push_str(&format!("\x1b[48;2;{};{};{}m{}", r, g, b, c));
+        }
+        out.push_str("\x1b[0m\n");
+    }
+
+    out
+}
+
+/// The same heatmap as a binary PPM image, with every cell drawn as a
+/// `scale` by `scale` block.
+pub fn render_heatmap_ppm(heightmap: &Heightmap, distance_map: &DistanceMap, scale: usize) -> Vec<u8> {
+    let max_distance = max_distance(distance_map);
+    let width = heightmap.num_cols() * scale;
+    let height = heightmap.num_rows() * scale;
+
+    let mut image = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    for y in 0..height {
+        for x in 0..width {
+            let (r, g, b) = colour(distance_map.distance((y / scale, x / scale)), max_distance);
+            image.extend([r, g, b]);
+        }
+    }
+
+    image
+}
@@ -0,0 +1,168 @@
+use std::{cmp::Ordering, fmt, slice};
+
+use crate::{json, Packet};
+
+/// Operands longer than this are cut short, so the walkthrough of a deeply
+/// nested pair stays linear in size.
+const MAX_OPERAND_LEN: usize = 100;
+/// Steps nested deeper than this are indented no further, with their depth
+/// written out instead.
+const MAX_INDENT_DEPTH: usize = 40;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum StepKind {
+    Compare(String, String),
+    ConvertLeft(String),
+    ConvertRight(String),
+    LeftSmaller,
+    RightSmaller,
+    LeftRanOut,
+    RightRanOut,
+}
+
+/// One line of a comparison walkthrough, indented by `depth`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Step {
+    pub depth: usize,
+    pub kind: StepKind,
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}- ", "  ".repeat(self.depth.min(MAX_INDENT_DEPTH)))?;
+        if self.depth > MAX_INDENT_DEPTH {
+            write!(f, "(depth {}) ", self.depth)?;
+        }
+        match &self.kind {
+            StepKind::Compare(left, right) => write!(f, "Compare {} vs {}", left, right),
+            StepKind::ConvertLeft(left) => write!(f, "Mixed types; convert left to {} and retry comparison", left),
+            StepKind::ConvertRight(right) => write!(f, "Mixed types; convert right to {} and retry comparison", right),
+            StepKind::LeftSmaller => write!(f, "Left side is smaller, so inputs are in the right order"),
+            StepKind::RightSmaller => write!(f, "Right side is smaller, so inputs are not in the right order"),
+            StepKind::LeftRanOut => write!(f, "Left side ran out of items, so inputs are in the right order"),
+            StepKind::RightRanOut => write!(f, "Right side ran out of items, so inputs are not in the right order"),
+        }
+    }
+}
+
+fn operand(packet: &Packet) -> String {
+    json::to_json_abbreviated(packet, MAX_OPERAND_LEN)
+}
+
+/// Lists being compared, along with the depth of the steps about their items.
+type Frame<'a> = (slice::Iter<'a, Packet>, slice::Iter<'a, Packet>, usize);
+
+/// Records every comparison made while ordering `left` against `right`, in
+/// the same shape as the puzzle's walkthrough. The last step gives the
+/// verdict unless the packets are equal. Like `Ord for Packet`, the lists
+/// being compared are kept on an explicit stack.
+pub fn explain(left: &Packet, right: &Packet) -> Vec<Step> {
+    let mut steps = vec![];
+    let mut stack: Vec<Frame> = vec![];
+    if compare(left, right, 0, &mut steps, &mut stack).is_some() {
+        return steps;
+    }
+
+    while let Some((left_vals, right_vals, depth)) = stack.last_mut() {
+        let depth = *depth;
+        match (left_vals.next(), right_vals.next()) {
+            (Some(left), Some(right)) => {
+                if compare(left, right, depth, &mut steps, &mut stack).is_some() {
+                    break;
+                }
+            },
+            (None, None) => {
+                stack.pop();
+            },
+            (None, Some(_)) => {
+                steps.push(Step { depth, kind: StepKind::LeftRanOut });
+                break;
+            },
+            (Some(_), None) => {
+                steps.push(Step { depth, kind: StepKind::RightRanOut });
+                break;
+            },
+        }
+    }
+
+    steps
+}
+
+/// Records the comparison of `left` against `right`. Two integers are
+/// settled at once, giving their ordering when they differ; lists are left
+/// on `stack` for their items to be compared.
+fn compare<'a>(
+    left: &'a Packet,
+    right: &'a Packet,
+    depth: usize,
+    steps: &mut Vec<Step>,
+    stack: &mut Vec<Frame<'a>>,
+) -> Option<Ordering> {
+    steps.push(Step { depth, kind: StepKind::Compare(operand(left), operand(right)) });
+    let depth = depth + 1;
+
+    match (left, right) {
+        (Packet::Integer(i), Packet::Integer(j)) => match i.cmp(j) {
+            Ordering::Less => {
+                steps.push(Step { depth, kind: StepKind::LeftSmaller });
+                Some(Ordering::Less)
+            },
+            Ordering::Greater => {
+                steps.push(Step { depth, kind: StepKind::RightSmaller });
+                Some(Ordering::Greater)
+            },
+            Ordering::Equal => None,
+        },
+        (Packet::List(left_vals), Packet::List(right_vals)) => {
+            stack.push((left_vals.iter(), right_vals.iter(), depth));
+            None
+        },
+        // the converted list is only described in text, its single element
+        // is compared in place
+        (Packet::Integer(_), Packet::List(right_vals)) => {
+            let converted = format!("[{}]", left);
+            steps.push(Step { depth, kind: StepKind::ConvertLeft(converted.clone()) });
+            steps.push(Step { depth, kind: StepKind::Compare(converted, operand(right)) });
+            stack.push((slice::from_ref(left).iter(), right_vals.iter(), depth + 1));
+            None
+        },
+        (Packet::List(left_vals), Packet::Integer(_)) => {
+            let converted = format!("[{}]", right);
+            steps.push(Step { depth, kind: StepKind::ConvertRight(converted.clone()) });
+            steps.push(Step { depth, kind: StepKind::Compare(operand(left), converted) });
+            stack.push((left_vals.iter(), slice::from_ref(right).iter(), depth + 1));
+            None
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::parse_packet;
+
+    fn step(depth: usize, kind: StepKind) -> Step {
+        Step { depth, kind }
+    }
+
+    fn compare_step(depth: usize, left: &str, right: &str) -> Step {
+        step(depth, StepKind::Compare(left.to_string(), right.to_string()))
+    }
+
+    #[test]
+    fn explains_example_pair_2() {
+        let left = parse_packet("[[1],[2,3,4]]").unwrap();
+        let right = parse_packet("[[1],4]").unwrap();
+
+        assert_eq!(explain(&left, &right), vec![
+            compare_step(0, "[[1],[2,3,4]]", "[[1],4]"),
+            compare_step(1, "[1]", "[1]"),
+            compare_step(2, "1", "1"),
+            compare_step(1, "[2,3,4]", "4"),
+            step(2, StepKind::ConvertRight("[4]".to_string())),
+            compare_step(2, "[2,3,4]", "[4]"),
+            compare_step(3, "2", "4"),
+            step(4, StepKind::LeftSmaller),
+        ]);
+    }
+}
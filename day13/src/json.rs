@@ -0,0 +1,194 @@
+use std::{fmt, slice};
+
+use crate::Packet;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct JsonError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: {}", self.line, self.column, self.message)
+    }
+}
+
+fn error(input: &str, pos: usize, message: impl Into<String>) -> JsonError {
+    let before = &input[..pos];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+    JsonError { line, column, message: message.into() }
+}
+
+fn skip_whitespace(bytes: &[u8], mut pos: usize) -> usize {
+    while matches!(bytes.get(pos), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+        pos += 1;
+    }
+    pos
+}
+
+fn parse_integer(input: &str, start: usize) -> Result<(u64, usize), JsonError> {
+    let bytes = input.as_bytes();
+    let mut end = start;
+    while matches!(bytes.get(end), Some(b'0'..=b'9')) {
+        end += 1;
+    }
+
+    let digits = &input[start..end];
+    if digits.len() > 1 && digits.starts_with('0') {
+        return Err(error(input, start, "numbers cannot have leading zeros"));
+    }
+    let value = digits.parse().map_err(|_| error(input, start, format!("{} is too large", digits)))?;
+    Ok((value, end))
+}
+
+/// Parses one packet from the start of `input`, returning it along with the
+/// byte offset just past it. Open lists are kept on an explicit stack, so
+/// the nesting depth is only limited by memory.
+fn parse_value(input: &str, pos: usize) -> Result<(Packet, usize), JsonError> {
+    let bytes = input.as_bytes();
+    let mut pos = pos;
+    let mut open_lists: Vec<Vec<Packet>> = vec![];
+
+    loop {
+        pos = skip_whitespace(bytes, pos);
+        let mut value = match bytes.get(pos) {
+            Some(b'[') => {
+                pos = skip_whitespace(bytes, pos + 1);
+                if bytes.get(pos) != Some(&b']') {
+                    open_lists.push(vec![]);
+                    continue;
+                }
+                pos += 1;
+                Packet::List(vec![])
+            },
+            Some(b'0'..=b'9') => {
+                let (value, end) = parse_integer(input, pos)?;
+                pos = end;
+                Packet::Integer(value)
+            },
+            Some(b'-') => return Err(error(input, pos, "packets only hold non-negative integers")),
+            Some(_) => {
+                let c = input[pos..].chars().next().unwrap();
+                return Err(error(input, pos, format!("expected a list or an integer, found {:?}", c)));
+            },
+            None => return Err(error(input, pos, "expected a list or an integer, found end of input")),
+        };
+
+        // attach the finished value to its list, closing as many lists as
+        // end here
+        loop {
+            let list = match open_lists.last_mut() {
+                Some(list) => list,
+                None => return Ok((value, pos)),
+            };
+            list.push(value);
+
+            pos = skip_whitespace(bytes, pos);
+            match bytes.get(pos) {
+                Some(b',') => {
+                    pos += 1;
+                    break;
+                },
+                Some(b']') => {
+                    pos += 1;
+                    value = Packet::List(open_lists.pop().unwrap());
+                },
+                _ => return Err(error(input, pos, "expected ',' or ']'")),
+            }
+        }
+    }
+}
+
+/// Parses a single packet, allowing whitespace around it but nothing else.
+pub fn parse_packet(input: &str) -> Result<Packet, JsonError> {
+    let (packet, end) = parse_value(input, 0)?;
+    let end = skip_whitespace(input.as_bytes(), end);
+    match end == input.len() {
+        true => Ok(packet),
+        false => Err(error(input, end, "unexpected trailing characters")),
+    }
+}
+
+/// Parses every packet in `input`. Packets may be separated by any
+/// whitespace, including blank lines, and may span several lines.
+pub fn parse_packets(input: &str) -> Result<Vec<Packet>, JsonError> {
+    let mut packets = vec![];
+    let mut pos = skip_whitespace(input.as_bytes(), 0);
+    while pos < input.len() {
+        let (packet, end) = parse_value(input, pos)?;
+        packets.push(packet);
+        pos = skip_whitespace(input.as_bytes(), end);
+    }
+
+    Ok(packets)
+}
+
+/// Writes `packet` as JSON. With an indent every element goes on its own
+/// line; without one the output has no whitespace at all, matching the
+/// puzzle input.
+pub fn to_json(packet: &Packet, indent: Option<&str>) -> String {
+    write_json(packet, indent, usize::MAX)
+}
+
+/// Compact JSON cut off with `...` once it grows past `max_len` bytes, so a
+/// huge packet costs no more to describe than a small one.
+pub fn to_json_abbreviated(packet: &Packet, max_len: usize) -> String {
+    write_json(packet, None, max_len)
+}
+
+fn write_json(packet: &Packet, indent: Option<&str>, max_len: usize) -> String {
+    let mut out = String::new();
+    let mut open_lists: Vec<slice::Iter<Packet>> = vec![];
+    let mut next = Some(packet);
+
+    loop {
+        if out.len() > max_len {
+            out.truncate(max_len);
+            out.push_str("...");
+            return out;
+        }
+
+        match next.take() {
+            Some(Packet::Integer(i)) => out.push_str(&i.to_string()),
+            Some(Packet::List(vals)) if vals.is_empty() => out.push_str("[]"),
+            Some(Packet::List(vals)) => {
+                out.push('[');
+                let mut children = vals.iter();
+                next = children.next();
+                open_lists.push(children);
+                new_line(&mut out, indent, open_lists.len());
+                continue;
+            },
+            None => (),
+        }
+
+        let children = match open_lists.last_mut() {
+            Some(children) => children,
+            None => return out,
+        };
+        match children.next() {
+            Some(child) => {
+                out.push(',');
+                new_line(&mut out, indent, open_lists.len());
+                next = Some(child);
+            },
+            None => {
+                open_lists.pop();
+                new_line(&mut out, indent, open_lists.len());
+                out.push(']');
+            },
+        }
+    }
+}
+
+fn new_line(out: &mut String, indent: Option<&str>, depth: usize) {
+    if let Some(indent) = indent {
+        out.push('\n');
+        for _ in 0..depth {
+            out.push_str(indent);
+        }
+    }
+}
@@ -1,116 +1,215 @@
-use std::{fs, cmp::Ordering};
+mod explain;
+mod json;
 
-use nom::{
-    branch::alt,
-    bytes::complete::tag,
-    character::complete::newline,
-    multi::{separated_list0, separated_list1},
-    sequence::delimited,
-    *,
-};
+use std::{env, fmt, fs, cmp::Ordering, mem, slice};
 
 #[derive(Debug)]
 enum Packet {
-    Integer(u32),
+    Integer(u64),
     List(Vec<Packet>),
 }
 
+/// Dropping a deeply nested packet recursively could overflow the stack, so
+/// nested lists are flattened onto the heap first.
+impl Drop for Packet {
+    fn drop(&mut self) {
+        if let Packet::List(vals) = self {
+            let mut to_drop = mem::take(vals);
+            while let Some(mut packet) = to_drop.pop() {
+                if let Packet::List(inner) = &mut packet {
+                    to_drop.append(inner);
+                }
+            }
+        }
+    }
+}
+
+/// Packets are ordered by the puzzle's rules. An integer compared against a
+/// list is treated as a one element list, which is borrowed in place with
+/// `slice::from_ref` rather than allocated. Lists being compared are kept on
+/// an explicit stack so deeply nested packets cannot overflow the call stack.
+impl Ord for Packet {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let mut stack = vec![(slice::from_ref(self).iter(), slice::from_ref(other).iter())];
+        while let Some((left_vals, right_vals)) = stack.last_mut() {
+            let (left, right) = match (left_vals.next(), right_vals.next()) {
+                (Some(left), Some(right)) => (left, right),
+                (None, None) => {
+                    stack.pop();
+                    continue;
+                },
+                (None, Some(_)) => return Ordering::Less,
+                (Some(_), None) => return Ordering::Greater,
+            };
+
+            match (left, right) {
+                (Packet::Integer(i), Packet::Integer(j)) => {
+                    if i != j {
+                        return i.cmp(j);
+                    }
+                },
+                (Packet::List(left_vals), Packet::List(right_vals)) => stack.push((left_vals.iter(), right_vals.iter())),
+                (Packet::Integer(_), Packet::List(right_vals)) => stack.push((slice::from_ref(left).iter(), right_vals.iter())),
+                (Packet::List(left_vals), Packet::Integer(_)) => stack.push((left_vals.iter(), slice::from_ref(right).iter())),
+            }
+        }
+
+        Ordering::Equal
+    }
+}
+
+impl PartialOrd for Packet {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Equality follows the ordering, so `2` and `[[2]]` are equal.
+impl PartialEq for Packet {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Packet {}
+
+/// Compact JSON, exactly as packets appear in the puzzle input.
+impl fmt::Display for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&json::to_json(self, None))
+    }
+}
+
+impl Packet {
+    fn to_pretty_json(&self) -> String {
+        json::to_json(self, Some("  "))
+    }
+}
+
 #[derive(Debug)]
 struct PacketPair {
     left: Packet,
     right: Packet,
 }
 
-#[derive(PartialEq, Eq)]
-enum CompareResult {
-    Correct,
-    Wrong,
-    Continue,
+/// Every packet plus the dividers in order. The flag marks the dividers;
+/// the sort is stable so a divider stays after any packet equal to it.
+fn sort_packets<'a>(packets: &[&'a Packet], dividers: &'a [Packet]) -> Vec<(&'a Packet, bool)> {
+    let mut sorted: Vec<(&Packet, bool)> = packets.iter()
+        .map(|p| (*p, false))
+        .chain(dividers.iter().map(|d| (d, true)))
+        .collect();
+    sorted.sort_by(|a, b| a.0.cmp(b.0));
+    sorted
 }
 
-fn compare_packets(left: &Packet, right: &Packet) -> CompareResult {
-    match (left, right) {
-        (Packet::Integer(i), Packet::Integer(j)) => {
-            match i.cmp(j) {
-                Ordering::Equal => CompareResult::Continue,
-                Ordering::Greater => CompareResult::Wrong,
-                Ordering::Less => CompareResult::Correct,
-            }
-        },
-        (Packet::List(left_vals), Packet::List(right_vals)) => {
-            for i in 0..left_vals.len() {
-                if i >= right_vals.len() {
-                    return CompareResult::Wrong
-                }
-
-                let r = compare_packets(&left_vals[i], &right_vals[i]);
-                if r == CompareResult::Correct || r == CompareResult::Wrong {
-                    return r;
-                }
-            }
+struct Options {
+    print_sorted: bool,
+    pretty: bool,
+    dividers: Vec<Packet>,
+    explain_pairs: Vec<usize>,
+}
 
-            if left_vals.len() < right_vals.len() {
-                CompareResult::Correct
-            } else {
-                CompareResult::Continue
-            }
-        },
-        (Packet::Integer(i), Packet::List(_)) => {
-            let list_val = Packet::List(vec![Packet::Integer(*i)]);
-            compare_packets(&list_val, right)
-        },
-        (Packet::List(_), Packet::Integer(j)) => {
-            let list_val = Packet::List(vec![Packet::Integer(*j)]);
-            compare_packets(left, &list_val)
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options { print_sorted: false, pretty: false, dividers: vec![], explain_pairs: vec![] };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--sorted" => options.print_sorted = true,
+            "--pretty" => options.pretty = true,
+            "--explain" => {
+                let value = args.next().ok_or("--explain expects a pair index")?;
+                let idx = value.parse().ok().filter(|idx| *idx > 0)
+                    .ok_or_else(|| format!("invalid pair index {:?}, pairs are numbered from 1", value))?;
+                options.explain_pairs.push(idx);
+            },
+            "--divider" => {
+                let value = args.next().ok_or("--divider expects a packet")?;
+                let divider = json::parse_packet(value)
+                    .map_err(|e| format!("invalid divider packet {:?}: {}", value, e))?;
+                options.dividers.push(divider);
+            },
+            _ => return Err(format!("unknown argument {:?}", arg)),
         }
     }
+
+    if options.dividers.is_empty() {
+        options.dividers = vec![
+            Packet::List(vec![Packet::List(vec![Packet::Integer(2)])]),
+            Packet::List(vec![Packet::List(vec![Packet::Integer(6)])]),
+        ];
+    }
+
+    Ok(options)
 }
 
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = parse_options(&args).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        eprintln!("usage: day13 [--sorted] [--pretty] [--divider PACKET]... [--explain PAIR]...");
+        std::process::exit(2);
+    });
+
     let input = fs::read_to_string("./src/input.txt").expect("Should be able to read input file");
-    let (_, packet_pairs) = parse_packet_pairs(&input).expect("Should be able to parse input");
+    let packet_pairs = parse_packet_pairs(&input).unwrap_or_else(|e| {
+        eprintln!("Could not parse input: {}", e);
+        std::process::exit(1);
+    });
+
+    for idx in &options.explain_pairs {
+        let pair = match packet_pairs.get(idx - 1) {
+            Some(pair) => pair,
+            None => {
+                eprintln!("There is no pair {}, the input has {} pairs", idx, packet_pairs.len());
+                std::process::exit(2);
+            }
+        };
+
+        println!("== Pair {} ==", idx);
+        for step in explain::explain(&pair.left, &pair.right) {
+            println!("{}", step);
+        }
+        println!();
+    }
 
     let part_1_ans = (1..=packet_pairs.len())
-        .filter(|idx| compare_packets(&packet_pairs[idx - 1].left, &packet_pairs[idx - 1].right) == CompareResult::Correct)
+        .filter(|idx| packet_pairs[idx - 1].left < packet_pairs[idx - 1].right)
         .sum::<usize>();
-    println!("{}", part_1_ans);
+    println!("Part 1: {}", part_1_ans);
 
     let all_packets: Vec<&Packet> = packet_pairs.iter()
         .flat_map(|p| vec![&p.left, &p.right])
         .collect();
-    
-    let divider_1 = Packet::List(vec![Packet::List(vec![Packet::Integer(2)])]);
-    let divider_2 = Packet::List(vec![Packet::List(vec![Packet::Integer(6)])]);
-
-    let divider_1_idx = all_packets.iter()
-        .filter(|p| compare_packets(p, &divider_1) == CompareResult::Correct)
-        .count() + 1;
-    let divider_2_idx = all_packets.iter()
-        .filter(|p| compare_packets(p, &divider_2) == CompareResult::Correct)
-        .count() + 2;
-
-    let part_2_ans = divider_1_idx * divider_2_idx; 
-    println!("{}", part_2_ans);
-}
-
-fn parse_packet(input: &str) -> IResult<&str, Packet> {
-    alt((
-        delimited(tag("["), separated_list0(tag(","), parse_packet), tag("]"))
-            .map(Packet::List),
-        nom::character::complete::u32
-            .map(Packet::Integer)
-    ))(input)
+    let sorted = sort_packets(&all_packets, &options.dividers);
+
+    let part_2_ans = sorted.iter()
+        .enumerate()
+        .filter(|(_, (_, is_divider))| *is_divider)
+        .map(|(idx, _)| idx + 1)
+        .product::<usize>();
+    println!("Part 2: {}", part_2_ans);
+
+    if options.print_sorted {
+        for (packet, _) in sorted {
+            match options.pretty {
+                true => println!("{}", packet.to_pretty_json()),
+                false => println!("{}", packet),
+            }
+        }
+    }
 }
 
-fn parse_packet_pair(input: &str) -> IResult<&str, PacketPair> {
-    let (input, left) = parse_packet(input)?;
-    let (input, _) = newline(input)?;
-    let (input, right) = parse_packet(input)?;
-    let (input, _) = newline(input)?;
-    Ok((input, PacketPair { left, right }))
-}
+fn parse_packet_pairs(input: &str) -> Result<Vec<PacketPair>, String> {
+    let mut packets = json::parse_packets(input).map_err(|e| e.to_string())?;
+    if packets.len() % 2 != 0 {
+        return Err(format!("expected packets in pairs, found {}", packets.len()));
+    }
 
-fn parse_packet_pairs(input: &str) -> IResult<&str, Vec<PacketPair>> {
-    separated_list1(newline, parse_packet_pair)(input)
+    let mut pairs = vec![];
+    let mut packets = packets.drain(..);
+    while let (Some(left), Some(right)) = (packets.next(), packets.next()) {
+        pairs.push(PacketPair { left, right });
+    }
+    Ok(pairs)
 }
-
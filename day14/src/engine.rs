@@ -0,0 +1,122 @@
+use std::collections::HashSet;
+
+use crate::{FloorMode, Point};
+
+/// Occupied cells of the cave, one bit per cell. The grid is wide enough
+/// that no grain can leave it sideways before reaching the floor.
+struct Grid {
+    min_x: i32,
+    min_y: i32,
+    width: usize,
+    bits: Vec<u64>,
+}
+
+impl Grid {
+    fn new(rock_points: &HashSet<Point>, source: Point, max_y: i32) -> Self {
+        // sand spreads at most one column per row it falls
+        let spread = max_y - source.y;
+        let min_x = rock_points.iter().map(|p| p.x).chain([source.x - spread]).min().unwrap();
+        let max_x = rock_points.iter().map(|p| p.x).chain([source.x + spread]).max().unwrap();
+        let min_y = rock_points.iter().map(|p| p.y).chain([source.y]).min().unwrap();
+
+        let width = (max_x - min_x + 1) as usize;
+        let height = (max_y - min_y + 1) as usize;
+        let mut grid = Grid { min_x, min_y, width, bits: vec![0; (width * height).div_ceil(64)] };
+        for rock in rock_points {
+            grid.set(*rock);
+        }
+        grid
+    }
+
+    fn index(&self, p: Point) -> usize {
+        (p.y - self.min_y) as usize * self.width + (p.x - self.min_x) as usize
+    }
+
+    fn is_set(&self, p: Point) -> bool {
+        let i = self.index(p);
+        self.bits[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    fn set(&mut self, p: Point) {
+        let i = self.index(p);
+        self.bits[i / 64] |= 1 << (i % 64);
+    }
+}
+
+/// The lowest rock and the row that acts as the floor.
+fn floor_y(rock_points: &HashSet<Point>, source: Point, mode: FloorMode) -> (i32, i32) {
+    let lowest_rock = rock_points.iter().map(|p| p.y).max().unwrap_or(source.y);
+    let max_y = match mode {
+        FloorMode::Abyss => lowest_rock + 2,
+        FloorMode::Floor(offset) => lowest_rock + offset,
+    };
+    (lowest_rock, max_y)
+}
+
+/// Drops grains from `source` until no more can come to rest and returns
+/// where they settled, in the order they did.
+///
+/// The cells a grain passes through are kept on a stack. The next grain
+/// follows the same path until the last cell that is still free, so it can
+/// resume from there instead of starting again at the source.
+///
+/// In abyss mode a floor is still placed two rows below the lowest rock: a
+/// grain can only reach it after falling past every rock, which is exactly
+/// when the pouring should stop.
+pub fn pour_sand(rock_points: &HashSet<Point>, source: Point, mode: FloorMode) -> Vec<Point> {
+    let (lowest_rock, max_y) = floor_y(rock_points, source, mode);
+    let mut grid = Grid::new(rock_points, source, max_y);
+    let mut sand = vec![];
+    if source.y >= max_y || grid.is_set(source) {
+        return sand;
+    }
+
+    let mut path = vec![source];
+    while let Some(&curr) = path.last() {
+        let below = curr.y + 1;
+        let next = [curr.x, curr.x - 1, curr.x + 1]
+            .into_iter()
+            .map(|x| Point { x, y: below })
+            .find(|p| below < max_y && !grid.is_set(*p));
+
+        match next {
+            Some(next) if mode == FloorMode::Abyss && next.y > lowest_rock => break,
+            Some(next) => path.push(next),
+            None => {
+                grid.set(curr);
+                sand.push(curr);
+                path.pop();
+            },
+        }
+    }
+
+    sand
+}
+
+/// With a floor, every cell the sand can reach from the source ends up
+/// filled, so the cells can be found row by row without dropping a single
+/// grain: a cell is reachable if it is free and one of the three cells
+/// above it is reachable.
+pub fn fill_from_source(rock_points: &HashSet<Point>, source: Point, floor_offset: i32) -> Vec<Point> {
+    let (_, max_y) = floor_y(rock_points, source, FloorMode::Floor(floor_offset));
+    let grid = Grid::new(rock_points, source, max_y);
+    if source.y >= max_y || grid.is_set(source) {
+        return vec![];
+    }
+
+    let mut filled = vec![source];
+    let mut row: Vec<i32> = vec![source.x];
+    for y in source.y + 1..max_y {
+        let mut next_row: Vec<i32> = row.iter()
+            .flat_map(|x| [x - 1, *x, x + 1])
+            .filter(|x| !grid.is_set(Point { x: *x, y }))
+            .collect();
+        next_row.sort_unstable();
+        next_row.dedup();
+
+        filled.extend(next_row.iter().map(|x| Point { x: *x, y }));
+        row = next_row;
+    }
+
+    filled
+}
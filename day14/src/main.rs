@@ -1,5 +1,9 @@
+mod engine;
+mod render;
+
 use std::{
     collections::HashSet,
+    env,
     fs,
     cmp::{max, min}
 };
@@ -18,6 +22,14 @@ struct Point {
     y: i32,
 }
 
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+enum FloorMode {
+    /// There is no floor; sand stops once a grain falls past the lowest rock.
+    Abyss,
+    /// An endless floor lies this many rows below the lowest rock.
+    Floor(i32),
+}
+
 type RockPath = Vec<Point>;
 
 fn points_in_path(path: &RockPath) -> Vec<Point> {
@@ -45,43 +57,111 @@ fn points_between(start: Point, end: Point) -> Vec<Point> {
     points
 }
 
-fn compute_sand_location(current_point: Point, rock_points: &HashSet<Point>, sand_points: &HashSet<Point>, max_y: i32) -> Option<Point> {
-    if rock_points.contains(&current_point) || sand_points.contains(&current_point) || current_point.y == max_y {
-        return None
-    }
+struct Options {
+    source: Point,
+    floor_offset: i32,
+    simulate_floor: bool,
+    draw: bool,
+    gif_path: Option<String>,
+    gif_part: u32,
+    grains_per_frame: usize,
+}
 
-    let locations_to_check_in_order = vec![
-        Point { x: current_point.x, y: current_point.y + 1 },
-        Point { x: current_point.x - 1, y: current_point.y + 1 },
-        Point { x: current_point.x + 1, y: current_point.y + 1 },
-    ];
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        source: Point { x: 500, y: 0 },
+        floor_offset: 2,
+        simulate_floor: false,
+        draw: false,
+        gif_path: None,
+        gif_part: 1,
+        grains_per_frame: 100,
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--simulate" => {
+                options.simulate_floor = true;
+                continue;
+            },
+            "--draw" => {
+                options.draw = true;
+                continue;
+            },
+            _ => (),
+        }
 
-    locations_to_check_in_order.iter()
-        .find_map(|p| compute_sand_location(*p, rock_points, sand_points, max_y))
-        .or(Some(current_point))
+        let value = args.next().ok_or_else(|| format!("{} expects a value", arg))?;
+        match arg.as_str() {
+            "--source" => {
+                let (_, source) = parse_point(value).map_err(|_| format!("invalid source {:?}, expected x,y", value))?;
+                options.source = source;
+            },
+            "--floor" => {
+                options.floor_offset = value.parse().ok().filter(|offset| *offset > 0)
+                    .ok_or_else(|| format!("invalid floor offset {:?}", value))?;
+            },
+            "--gif" => options.gif_path = Some(value.clone()),
+            "--gif-part" => {
+                options.gif_part = value.parse().ok().filter(|part| *part == 1 || *part == 2)
+                    .ok_or_else(|| format!("invalid part {:?}, expected 1 or 2", value))?;
+            },
+            "--every" => {
+                options.grains_per_frame = value.parse().ok().filter(|n| *n > 0)
+                    .ok_or_else(|| format!("invalid grain count {:?}", value))?;
+            },
+            _ => return Err(format!("unknown argument {:?}", arg)),
+        }
+    }
+
+    Ok(options)
 }
 
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = parse_options(&args).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        eprintln!("usage: day14 [--source x,y] [--floor OFFSET] [--simulate] [--draw] [--gif PATH] [--gif-part 1|2] [--every N]");
+        std::process::exit(2);
+    });
+
     let input = fs::read_to_string("./src/input.txt").expect("Should be able to read input file");
     
     let (_, rock_paths) = parse_rock_paths(&input).expect("Should be able to parse input");
     let mut rock_points: HashSet<Point> = HashSet::new();
-    let mut sand_points: HashSet<Point> = HashSet::new();
     for path in rock_paths.iter() {
         let points = points_in_path(path);
         rock_points.extend(points);
     }
 
-    let starting_point = Point { x: 500, y: 0 };
-    let max_y = rock_points.iter().max_by_key(|p| p.y).unwrap().y + 2;
-    for i in 1.. {
-        let resting_point = compute_sand_location(starting_point, &rock_points, &sand_points, max_y).unwrap();
-        if resting_point == starting_point {
-            println!("{}", i);
-            break;
-        } else {
-            sand_points.insert(resting_point);
-        }
+    let part_1_sand = engine::pour_sand(&rock_points, options.source, FloorMode::Abyss);
+    println!("Part 1: {}", part_1_sand.len());
+
+    // the animation needs grains in the order they come to rest
+    let simulate_floor = options.simulate_floor || (options.gif_path.is_some() && options.gif_part == 2);
+    let part_2_sand = match simulate_floor {
+        true => engine::pour_sand(&rock_points, options.source, FloorMode::Floor(options.floor_offset)),
+        false => engine::fill_from_source(&rock_points, options.source, options.floor_offset),
+    };
+    println!("Part 2: {}", part_2_sand.len());
+
+    let lowest_rock = rock_points.iter().map(|p| p.y).max().unwrap_or(options.source.y);
+    let floor_y = lowest_rock + options.floor_offset;
+    if options.draw {
+        println!("{}", render::render_ascii(&rock_points, &part_1_sand, options.source, None));
+        println!("{}", render::render_ascii(&rock_points, &part_2_sand, options.source, Some(floor_y)));
+    }
+
+    if let Some(path) = &options.gif_path {
+        let (sand, floor_y) = match options.gif_part {
+            1 => (&part_1_sand, None),
+            _ => (&part_2_sand, Some(floor_y)),
+        };
+        render::write_gif(path, &rock_points, sand, options.source, floor_y, options.grains_per_frame, 4)
+            .unwrap_or_else(|e| {
+                eprintln!("Could not write gif: {}", e);
+                std::process::exit(1);
+            });
     }
 }
 
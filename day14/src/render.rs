@@ -0,0 +1,139 @@
+use std::{collections::HashSet, fs::File, io};
+
+use gif::{Encoder, Frame, Repeat};
+
+use crate::Point;
+
+const AIR: u8 = 0;
+const ROCK: u8 = 1;
+const SAND: u8 = 2;
+const SOURCE: u8 = 3;
+const PALETTE: [u8; 12] = [
+    20, 20, 30,
+    120, 120, 120,
+    230, 190, 90,
+    220, 50, 50,
+];
+
+/// The smallest box holding every rock, grain and the source, plus the
+/// floor row when there is one.
+struct Bounds {
+    min_x: i32,
+    max_x: i32,
+    min_y: i32,
+    max_y: i32,
+}
+
+impl Bounds {
+    fn new(rock_points: &HashSet<Point>, sand: &[Point], source: Point, floor_y: Option<i32>) -> Self {
+        let points = || rock_points.iter().chain(sand).chain([&source]);
+        Bounds {
+            min_x: points().map(|p| p.x).min().unwrap(),
+            max_x: points().map(|p| p.x).max().unwrap(),
+            min_y: points().map(|p| p.y).min().unwrap(),
+            max_y: points().map(|p| p.y).chain(floor_y).max().unwrap(),
+        }
+    }
+
+    fn width(&self) -> usize {
+        (self.max_x - self.min_x + 1) as usize
+    }
+
+    fn height(&self) -> usize {
+        (self.max_y - self.min_y + 1) as usize
+    }
+
+    fn index(&self, p: Point) -> usize {
+        (p.y - self.min_y) as usize * self.width() + (p.x - self.min_x) as usize
+    }
+}
+
+/// Cell kinds for every cell in `bounds`, row by row.
+fn background(bounds: &Bounds, rock_points: &HashSet<Point>, source: Point, floor_y: Option<i32>) -> Vec<u8> {
+    let mut cells = vec![AIR; bounds.width() * bounds.height()];
+    for rock in rock_points {
+        cells[bounds.index(*rock)] = ROCK;
+    }
+    if let Some(y) = floor_y {
+        for x in bounds.min_x..=bounds.max_x {
+            cells[bounds.index(Point { x, y })] = ROCK;
+        }
+    }
+    cells[bounds.index(source)] = SOURCE;
+    cells
+}
+
+/// Draws the cave as in the puzzle: `#` for rock, `o` for sand, `+` for the
+/// source and `.` for air, cropped to what is in use.
+pub fn render_ascii(rock_points: &HashSet<Point>, sand: &[Point], source: Point, floor_y: Option<i32>) -> String {
+    let bounds = Bounds::new(rock_points, sand, source, floor_y);
+    let mut cells = background(&bounds, rock_points, source, floor_y);
+    for grain in sand {
+        cells[bounds.index(*grain)] = SAND;
+    }
+
+    cells.chunks(bounds.width())
+        .map(|row| {
+            row.iter()
+                .map(|cell| match *cell {
+                    ROCK => '#',
+                    SAND => 'o',
+                    SOURCE => '+',
+                    _ => '.',
+                })
+                .chain(['\n'])
+                .collect::<String>()
+        })
+        .collect()
+}
+
+/// Writes an animated GIF of the sand piling up, adding `grains_per_frame`
+/// grains in each frame. Every cell is drawn as a `scale` by `scale` block.
+pub fn write_gif(
+    path: &str,
+    rock_points: &HashSet<Point>,
+    sand: &[Point],
+    source: Point,
+    floor_y: Option<i32>,
+    grains_per_frame: usize,
+    scale: usize,
+) -> io::Result<()> {
+    let bounds = Bounds::new(rock_points, sand, source, floor_y);
+    let width = bounds.width() * scale;
+    let height = bounds.height() * scale;
+    let (gif_width, gif_height) = match (u16::try_from(width), u16::try_from(height)) {
+        (Ok(w), Ok(h)) => (w, h),
+        _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, "cave is too large for a gif, try a smaller scale")),
+    };
+
+    let mut encoder = Encoder::new(File::create(path)?, gif_width, gif_height, &PALETTE).map_err(io::Error::other)?;
+    encoder.set_repeat(Repeat::Infinite).map_err(io::Error::other)?;
+
+    let mut cells = background(&bounds, rock_points, source, floor_y);
+    let chunks: Vec<&[Point]> = match sand.is_empty() {
+        true => vec![&[]],
+        false => sand.chunks(grains_per_frame.max(1)).collect(),
+    };
+    for (i, grains) in chunks.iter().enumerate() {
+        for grain in *grains {
+            cells[bounds.index(*grain)] = SAND;
+        }
+
+        let mut pixels = Vec::with_capacity(width * height);
+        for row in cells.chunks(bounds.width()) {
+            let scaled_row: Vec<u8> = row.iter()
+                .flat_map(|cell| std::iter::repeat_n(*cell, scale))
+                .collect();
+            for _ in 0..scale {
+                pixels.extend(&scaled_row);
+            }
+        }
+
+        let mut frame = Frame::from_indexed_pixels(gif_width, gif_height, pixels, None);
+        // hold the finished cave on screen for a while before looping
+        frame.delay = if i + 1 == chunks.len() { 300 } else { 4 };
+        encoder.write_frame(&frame).map_err(io::Error::other)?;
+    }
+
+    Ok(())
+}
@@ -1,4 +1,8 @@
+mod render;
+
 use std::{
+    collections::HashSet,
+    env,
     fs,
     ops::RangeInclusive,
     cmp::{min, max}
@@ -31,6 +35,10 @@ impl SensorBeaconPair {
         }
     }
 
+    fn covers(&self, point: &Point) -> bool {
+        manhatten_distance(&self.sensor_position, point) <= self.distance_between
+    }
+
     fn marked_column_range_for_row(
         &self,
         row: i32,
@@ -44,17 +52,25 @@ impl SensorBeaconPair {
             let col_offset = self.distance_between - distance_to_row;
             let col_lower_bound = max(min_col, self.sensor_position.x - col_offset);
             let col_upper_bound = min(max_col, self.sensor_position.x + col_offset);
-            Some(RangeInclusive::new(col_lower_bound, col_upper_bound))
+            let range = RangeInclusive::new(col_lower_bound, col_upper_bound);
+            // the covered columns may lie entirely outside the bounds
+            match range.is_empty() {
+                true => None,
+                false => Some(range),
+            }
         }
     }
 }
 
 fn sort_merge_ranges(mut ranges: Vec<RangeInclusive<i32>>) -> Vec<RangeInclusive<i32>> {
+    if ranges.is_empty() {
+        return ranges;
+    }
+
     ranges.sort_by_key(|r| *r.start());
     let mut result = vec![];
     let mut current_range = ranges[0].clone();
-    for i in 1..ranges.len() {
-        let range = ranges[i].clone();
+    for range in ranges.into_iter().skip(1) {
         if range.start() <= current_range.end() {
             let new_end = max(*range.end(), *current_range.end());
             current_range = RangeInclusive::new(*current_range.start(), new_end);
@@ -68,41 +84,108 @@ fn sort_merge_ranges(mut ranges: Vec<RangeInclusive<i32>>) -> Vec<RangeInclusive
     result
 }
 
+/// Every part of `desired_range` not covered by `ranges`, which must be
+/// sorted and merged.
+fn missing_ranges(
+    ranges: &[RangeInclusive<i32>],
+    desired_range: &RangeInclusive<i32>
+) -> Vec<RangeInclusive<i32>> {
+    let mut missing = vec![];
+    let mut next_expected = *desired_range.start();
+    for range in ranges {
+        if *range.start() > next_expected {
+            missing.push(RangeInclusive::new(next_expected, range.start() - 1));
+        }
+
+        next_expected = max(next_expected, range.end() + 1);
+    }
+
+    if next_expected <= *desired_range.end() {
+        missing.push(RangeInclusive::new(next_expected, *desired_range.end()));
+    }
+    missing
+}
+
 fn find_missing(
     ranges: Vec<RangeInclusive<i32>>,
     desired_range: &RangeInclusive<i32>
 ) -> Option<i32> {
-    if ranges.len() == 1 && &ranges[0] == desired_range {
-        return None
-    }
+    missing_ranges(&ranges, desired_range)
+        .first()
+        .map(|range| *range.start())
+}
 
-    let mut next_expected = *desired_range.start();
-    for range in ranges {
-        if *range.start() != next_expected {
-            return Some(next_expected)
+/// A block of cells, bounds inclusive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rectangle {
+    min_x: i32,
+    max_x: i32,
+    min_y: i32,
+    max_y: i32,
+}
+
+/// Lists every cell in the square from `min_coord` to `max_coord` that no
+/// sensor covers. Each row's gaps are found from the merged sensor ranges,
+/// and a gap spanning the same columns as one in the row above extends
+/// that rectangle downwards.
+fn uncovered_regions(sensor_beacon_pairs: &[SensorBeaconPair], min_coord: i32, max_coord: i32) -> Vec<Rectangle> {
+    let desired_range = RangeInclusive::new(min_coord, max_coord);
+    let mut closed: Vec<Rectangle> = vec![];
+    let mut open: Vec<Rectangle> = vec![];
+    for row in min_coord..=max_coord {
+        let ranges = sensor_beacon_pairs.iter()
+            .filter_map(|p| p.marked_column_range_for_row(row, min_coord, max_coord))
+            .collect::<Vec<RangeInclusive<i32>>>();
+        let gaps = missing_ranges(&sort_merge_ranges(ranges), &desired_range);
+
+        let mut still_open = vec![];
+        for gap in gaps {
+            let continued = open.iter()
+                .position(|r| r.min_x == *gap.start() && r.max_x == *gap.end());
+            match continued {
+                Some(idx) => {
+                    let mut rectangle = open.swap_remove(idx);
+                    rectangle.max_y = row;
+                    still_open.push(rectangle);
+                },
+                None => still_open.push(Rectangle { min_x: *gap.start(), max_x: *gap.end(), min_y: row, max_y: row }),
+            }
         }
 
-        next_expected = range.end() + 1;
+        closed.append(&mut open);
+        open = still_open;
     }
 
-    if next_expected != *desired_range.end() + 1 {
-        Some(next_expected)
-    } else {
-        None
-    }
+    closed.append(&mut open);
+    closed.sort_by_key(|r| (r.min_y, r.min_x));
+    closed
+}
+
+/// Counts the positions in `row` that cannot hold a beacon: every position
+/// some sensor covers, except those where a beacon already is.
+fn count_covered_in_row(sensor_beacon_pairs: &[SensorBeaconPair], row: i32) -> i64 {
+    let ranges = sensor_beacon_pairs.iter()
+        .filter_map(|p| p.marked_column_range_for_row(row, i32::MIN, i32::MAX))
+        .collect::<Vec<RangeInclusive<i32>>>();
+    let covered: i64 = sort_merge_ranges(ranges).iter()
+        .map(|r| i64::from(*r.end()) - i64::from(*r.start()) + 1)
+        .sum();
+
+    let beacons_in_row: HashSet<i32> = sensor_beacon_pairs.iter()
+        .filter(|p| p.beacon_position.y == row)
+        .map(|p| p.beacon_position.x)
+        .collect();
+
+    covered - beacons_in_row.len() as i64
 }
 
 fn manhatten_distance(p1: &Point, p2: &Point) -> i32 {
     (p1.x - p2.x).abs() + (p1.y - p2.y).abs()
 }
 
-fn main() {
-    let input = fs::read_to_string("./src/input.txt").expect("Should be able to read input file");
-    let (_, sensor_beacon_pairs) = parse_sensor_beacon_pairs(&input).expect("Should be able to parse input");
-    
-    let min_coord = 0;
-    let max_coord = 4000000;
-    let tuning_frequency_multiplier: i64 = 4000000;
+/// Finds the uncovered position by sweeping every row. Kept as a slow but
+/// obviously correct cross-check for `find_distress_beacon`.
+fn scan_for_distress_beacon(sensor_beacon_pairs: &[SensorBeaconPair], min_coord: i32, max_coord: i32) -> Option<Point> {
     let desired_range = RangeInclusive::new(min_coord, max_coord);
     for row in min_coord..=max_coord {
         let ranges = sensor_beacon_pairs.iter() 
@@ -111,13 +194,166 @@ fn main() {
 
         let sorted_merged_ranges = sort_merge_ranges(ranges);
         if let Some(missing_col) = find_missing(sorted_merged_ranges, &desired_range) {
-            let tuning_frequency = i64::from(missing_col) * tuning_frequency_multiplier + i64::from(row); 
-            println!("{}", tuning_frequency);
-            return
+            return Some(Point { x: missing_col, y: row });
+        }
+    }
+
+    None
+}
+
+/// Finds the uncovered position without looking at every row.
+///
+/// Rotating to u = x + y and v = y - x turns each sensor's diamond into a
+/// square: the cells with u in `s - d..=s + d` and v likewise. Cutting the
+/// u and v axes wherever a square starts or stops gives a grid of
+/// rectangles, each either wholly inside or wholly outside every square, so
+/// testing one cell per rectangle is enough, however large the area is.
+/// Only (u, v) with u + v even are real cells, and the search area is a
+/// diamond in these coordinates, so the cell tested has to be picked with
+/// both in mind.
+fn find_distress_beacon(sensor_beacon_pairs: &[SensorBeaconPair], min_coord: i32, max_coord: i32) -> Option<Point> {
+    let (min_coord, max_coord) = (i64::from(min_coord), i64::from(max_coord));
+    let mut u_cuts: Vec<i64> = vec![2 * min_coord, 2 * max_coord + 1];
+    let mut v_cuts: Vec<i64> = vec![min_coord - max_coord, max_coord - min_coord + 1];
+    for pair in sensor_beacon_pairs {
+        let (sx, sy) = (i64::from(pair.sensor_position.x), i64::from(pair.sensor_position.y));
+        let d = i64::from(pair.distance_between);
+        u_cuts.extend([sx + sy - d, sx + sy + d + 1]);
+        v_cuts.extend([sy - sx - d, sy - sx + d + 1]);
+    }
+    let intervals = |mut cuts: Vec<i64>| {
+        cuts.sort_unstable();
+        cuts.dedup();
+        cuts.windows(2).map(|w| (w[0], w[1] - 1)).collect::<Vec<(i64, i64)>>()
+    };
+    let (u_intervals, v_intervals) = (intervals(u_cuts), intervals(v_cuts));
+
+    u_intervals.iter()
+        .flat_map(|u| v_intervals.iter().map(move |v| (*u, *v)))
+        .filter_map(|(u, v)| cell_in_area(u, v, min_coord, max_coord))
+        .map(|(x, y)| Point { x: x as i32, y: y as i32 })
+        .find(|candidate| sensor_beacon_pairs.iter().all(|p| !p.covers(candidate)))
+}
+
+/// Some cell (x, y) of the search area whose rotated coordinates lie in
+/// `u_range` and `v_range`.
+///
+/// For a fixed u the area allows v in `max(2 * min - u, u - 2 * max)..=
+/// min(2 * max - u, u - 2 * min)`, which is widest at u = min + max and
+/// narrows away from it. So if any u works, the one nearest the middle
+/// does, or one next to it when the only v there has the wrong parity.
+fn cell_in_area((u_start, u_end): (i64, i64), (v_start, v_end): (i64, i64), min_coord: i64, max_coord: i64) -> Option<(i64, i64)> {
+    let middle = (min_coord + max_coord).clamp(u_start, u_end);
+    [middle, middle - 1, middle + 1]
+        .into_iter()
+        .filter(|u| (u_start..=u_end).contains(u))
+        .find_map(|u| {
+            let low = v_start.max(2 * min_coord - u).max(u - 2 * max_coord);
+            let high = v_end.min(2 * max_coord - u).min(u - 2 * min_coord);
+            let v = low + (u + low).rem_euclid(2);
+            match v <= high {
+                true => Some(((u - v) / 2, (u + v) / 2)),
+                false => None,
+            }
+        })
+}
+
+struct Options {
+    row: i32,
+    min_coord: i32,
+    max_coord: i32,
+    tuning_frequency_multiplier: i64,
+    scan: bool,
+    list_uncovered: bool,
+    svg_path: Option<String>,
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        row: 2000000,
+        min_coord: 0,
+        max_coord: 4000000,
+        tuning_frequency_multiplier: 4000000,
+        scan: false,
+        list_uncovered: false,
+        svg_path: None,
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--scan" => {
+                options.scan = true;
+                continue;
+            },
+            "--uncovered" => {
+                options.list_uncovered = true;
+                continue;
+            },
+            _ => (),
+        }
+
+        let value = args.next().ok_or_else(|| format!("{} expects a value", arg))?;
+        let invalid = || format!("invalid value {:?} for {}", value, arg);
+        match arg.as_str() {
+            "--row" => options.row = value.parse().map_err(|_| invalid())?,
+            "--min" => options.min_coord = value.parse().map_err(|_| invalid())?,
+            "--max" => options.max_coord = value.parse().map_err(|_| invalid())?,
+            "--multiplier" => options.tuning_frequency_multiplier = value.parse().map_err(|_| invalid())?,
+            "--svg" => options.svg_path = Some(value.clone()),
+            _ => return Err(format!("unknown argument {:?}", arg)),
         }
     }
 
-    println!("NO ANSWER FOUND!!!!");
+    if options.min_coord > options.max_coord {
+        return Err("--min must not be larger than --max".to_string());
+    }
+    Ok(options)
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = parse_options(&args).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        eprintln!("usage: day15 [--row N] [--min N] [--max N] [--multiplier N] [--scan] [--uncovered] [--svg PATH]");
+        std::process::exit(2);
+    });
+
+    let input = fs::read_to_string("./src/input.txt").expect("Should be able to read input file");
+    let (_, sensor_beacon_pairs) = parse_sensor_beacon_pairs(&input).expect("Should be able to parse input");
+
+    println!("Part 1: {}", count_covered_in_row(&sensor_beacon_pairs, options.row));
+
+    let distress_beacon = match options.scan {
+        true => scan_for_distress_beacon(&sensor_beacon_pairs, options.min_coord, options.max_coord),
+        false => find_distress_beacon(&sensor_beacon_pairs, options.min_coord, options.max_coord),
+    };
+    match distress_beacon {
+        Some(beacon) => {
+            let tuning_frequency = i64::from(beacon.x) * options.tuning_frequency_multiplier + i64::from(beacon.y);
+            println!("Part 2: {}", tuning_frequency);
+        },
+        None => println!("Part 2: no distress beacon found"),
+    }
+
+    if options.list_uncovered || options.svg_path.is_some() {
+        let uncovered = uncovered_regions(&sensor_beacon_pairs, options.min_coord, options.max_coord);
+        if options.list_uncovered {
+            for region in &uncovered {
+                println!("x={}..={}, y={}..={}", region.min_x, region.max_x, region.min_y, region.max_y);
+            }
+        }
+
+        if let Some(path) = &options.svg_path {
+            let area = Rectangle {
+                min_x: options.min_coord,
+                max_x: options.max_coord,
+                min_y: options.min_coord,
+                max_y: options.max_coord,
+            };
+            let svg = render::render_coverage_svg(&sensor_beacon_pairs, &area, &uncovered);
+            fs::write(path, svg).expect("Should be able to write svg file");
+        }
+    }
 }
 
 fn parse_sensor_beacon_pairs(input: &str) -> IResult<&str, Vec<SensorBeaconPair>> {
@@ -0,0 +1,102 @@
+use crate::{Rectangle, SensorBeaconPair};
+
+const WIDTH_PX: f64 = 1000.0;
+
+/// Draws the sensors, their beacons and the diamond each sensor covers,
+/// with the search area outlined and its uncovered cells in red. The view
+/// is scaled to fit every diamond, so markers are sized relative to it.
+pub fn render_coverage_svg(
+    sensor_beacon_pairs: &[SensorBeaconPair],
+    area: &Rectangle,
+    uncovered: &[Rectangle],
+) -> String {
+    let mut min_x = f64::from(area.min_x);
+    let mut max_x = f64::from(area.max_x) + 1.0;
+    let mut min_y = f64::from(area.min_y);
+    let mut max_y = f64::from(area.max_y) + 1.0;
+    for pair in sensor_beacon_pairs {
+        let (sx, sy, r) = sensor_extent(pair);
+        min_x = min_x.min(sx - r);
+        max_x = max_x.max(sx + r + 1.0);
+        min_y = min_y.min(sy - r);
+        max_y = max_y.max(sy + r + 1.0);
+    }
+
+    let extent = (max_x - min_x).max(max_y - min_y);
+    let margin = extent * 0.02;
+    let marker = extent / 250.0;
+    let (view_x, view_y) = (min_x - margin, min_y - margin);
+    let (view_width, view_height) = (max_x - min_x + 2.0 * margin, max_y - min_y + 2.0 * margin);
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"{} {} {} {}\">\n",
+        WIDTH_PX, (WIDTH_PX * view_height / view_width).round(), view_x, view_y, view_width, view_height,
+    );
+    svg.push_str(&format!(
+        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"white\"/>\n",
+        view_x, view_y, view_width, view_height,
+    ));
+
+    for (i, pair) in sensor_beacon_pairs.iter().enumerate() {
+        let (sx, sy, r) = sensor_extent(pair);
+        let hue = (i * 47) % 360;
+        svg.push_str(&format!(
+            "<polygon points=\"{},{} {},{} {},{} {},{}\" fill=\"hsl({},70%,60%)\" fill-opacity=\"0.25\" stroke=\"hsl({},70%,40%)\" vector-effect=\"non-scaling-stroke\"/>\n",
+            sx + 0.5, sy - r,
+            sx + r + 1.0, sy + 0.5,
+            sx + 0.5, sy + r + 1.0,
+            sx - r, sy + 0.5,
+            hue, hue,
+        ));
+    }
+
+    svg.push_str(&format!(
+        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"none\" stroke=\"black\" stroke-dasharray=\"4 4\" vector-effect=\"non-scaling-stroke\"/>\n",
+        area.min_x, area.min_y, area.max_x - area.min_x + 1, area.max_y - area.min_y + 1,
+    ));
+
+    for pair in sensor_beacon_pairs {
+        let (sx, sy) = (f64::from(pair.sensor_position.x) + 0.5, f64::from(pair.sensor_position.y) + 0.5);
+        let (bx, by) = (f64::from(pair.beacon_position.x) + 0.5, f64::from(pair.beacon_position.y) + 0.5);
+        svg.push_str(&format!(
+            "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"grey\" vector-effect=\"non-scaling-stroke\"/>\n",
+            sx, sy, bx, by,
+        ));
+        svg.push_str(&format!(
+            "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"black\"><title>sensor {},{}</title></circle>\n",
+            sx, sy, marker, pair.sensor_position.x, pair.sensor_position.y,
+        ));
+        svg.push_str(&format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"blue\"><title>beacon {},{}</title></rect>\n",
+            bx - marker, by - marker, 2.0 * marker, 2.0 * marker, pair.beacon_position.x, pair.beacon_position.y,
+        ));
+    }
+
+    // uncovered cells are usually far too small to see, so each region also
+    // gets a ring around it
+    for region in uncovered {
+        let (width, height) = (region.max_x - region.min_x + 1, region.max_y - region.min_y + 1);
+        svg.push_str(&format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"red\"/>\n",
+            region.min_x, region.min_y, width, height,
+        ));
+        svg.push_str(&format!(
+            "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"none\" stroke=\"red\" stroke-width=\"2\" vector-effect=\"non-scaling-stroke\"><title>uncovered {},{} to {},{}</title></circle>\n",
+            f64::from(region.min_x) + f64::from(width) / 2.0,
+            f64::from(region.min_y) + f64::from(height) / 2.0,
+            marker * 3.0,
+            region.min_x, region.min_y, region.max_x, region.max_y,
+        ));
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn sensor_extent(pair: &SensorBeaconPair) -> (f64, f64, f64) {
+    (
+        f64::from(pair.sensor_position.x),
+        f64::from(pair.sensor_position.y),
+        f64::from(pair.distance_between),
+    )
+}
@@ -0,0 +1,117 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{schedule::Opening, Network, Node};
+
+const AGENT_COLORS: [&str; 6] = ["red", "blue", "darkgreen", "darkorange", "purple", "brown"];
+
+/// Draws the tunnels and the compressed graph of valves worth opening side
+/// by side, both with flow rates in the labels. Each agent's route is drawn
+/// in its own color: along the tunnels it walks through, and along the
+/// edges it takes between the valves it opens.
+pub fn render_dot(network: &Network, start: Node, routes: &[Vec<Opening>]) -> String {
+    let route_edges = route_edges(start, routes);
+    let tunnel_edges = tunnel_edges(network, &route_edges);
+    let opened_by: HashMap<Node, usize> = routes.iter()
+        .enumerate()
+        .flat_map(|(agent, route)| route.iter().map(move |o| (o.valve, agent)))
+        .collect();
+
+    let mut dot = String::from("graph valves {\n  node [shape=circle, fontsize=10];\n");
+
+    dot.push_str("  subgraph cluster_tunnels {\n    label=\"tunnels\";\n");
+    for node in 0..network.num_nodes() {
+        dot.push_str(&format!("    {}\n", node_statement("t", network, node, start, &opened_by)));
+    }
+    for u in 0..network.num_nodes() {
+        for v in network.neighbors[u].iter().copied().filter(|v| u < *v) {
+            let style = match tunnel_edges.get(&(u, v)) {
+                Some(agent) => format!(" [color={}, penwidth=3]", color(*agent)),
+                None => String::new(),
+            };
+            dot.push_str(&format!("    t_{} -- t_{}{};\n", network.valves[u], network.valves[v], style));
+        }
+    }
+    dot.push_str("  }\n");
+
+    let mut nodes = network.nodes_of_interest();
+    if !nodes.contains(&start) {
+        nodes.insert(0, start);
+    }
+    dot.push_str("  subgraph cluster_compressed {\n    label=\"valves worth opening, by travel time\";\n");
+    for node in nodes.iter().copied() {
+        dot.push_str(&format!("    {}\n", node_statement("c", network, node, start, &opened_by)));
+    }
+    for (i, u) in nodes.iter().copied().enumerate() {
+        for v in nodes[i + 1..].iter().copied() {
+            let distance = network.dist_matrix[u][v];
+            if distance == i32::MAX {
+                continue;
+            }
+
+            let style = match route_edges.get(&ordered(u, v)) {
+                Some(agent) => format!(", color={}, fontcolor={}, penwidth=3", color(*agent), color(*agent)),
+                None => ", color=grey80, fontcolor=grey50".to_string(),
+            };
+            dot.push_str(&format!(
+                "    c_{} -- c_{} [label={}{}];\n",
+                network.valves[u], network.valves[v], distance, style,
+            ));
+        }
+    }
+    dot.push_str("  }\n}\n");
+
+    dot
+}
+
+fn node_statement(prefix: &str, network: &Network, node: Node, start: Node, opened_by: &HashMap<Node, usize>) -> String {
+    let valve = &network.valves[node];
+    let mut attributes = vec![format!("label=\"{}\\n{}\"", valve, network.flow_rates[node])];
+    if node == start {
+        attributes.push("shape=doublecircle".to_string());
+    }
+    if let Some(agent) = opened_by.get(&node) {
+        attributes.push(format!("color={}, penwidth=2", color(*agent)));
+    } else if network.flow_rates[node] == 0 {
+        attributes.push("fontcolor=grey50, color=grey50".to_string());
+    }
+    format!("{}_{} [{}];", prefix, valve, attributes.join(", "))
+}
+
+fn color(agent: usize) -> &'static str {
+    AGENT_COLORS[agent % AGENT_COLORS.len()]
+}
+
+fn ordered(u: Node, v: Node) -> (Node, Node) {
+    (u.min(v), u.max(v))
+}
+
+/// Pairs of consecutive valves on each route, keyed by the agent taking them.
+fn route_edges(start: Node, routes: &[Vec<Opening>]) -> BTreeMap<(Node, Node), usize> {
+    let mut edges = BTreeMap::new();
+    for (agent, route) in routes.iter().enumerate() {
+        let mut prev = start;
+        for opening in route {
+            edges.entry(ordered(prev, opening.valve)).or_insert(agent);
+            prev = opening.valve;
+        }
+    }
+    edges
+}
+
+/// The tunnels walked along each route edge, following a shortest path.
+fn tunnel_edges(network: &Network, route_edges: &BTreeMap<(Node, Node), usize>) -> BTreeMap<(Node, Node), usize> {
+    let mut edges = BTreeMap::new();
+    for (&(from, to), &agent) in route_edges {
+        let mut curr = from;
+        while curr != to {
+            let remaining = network.dist_matrix[curr][to];
+            let next = network.neighbors[curr].iter()
+                .copied()
+                .find(|n| network.dist_matrix[*n][to] == remaining - 1)
+                .expect("Route edges should only join reachable valves");
+            edges.entry(ordered(curr, next)).or_insert(agent);
+            curr = next;
+        }
+    }
+    edges
+}
@@ -1,11 +1,13 @@
+mod dot;
+mod schedule;
+
 use std::{
-    collections::{HashMap, HashSet},
+    collections::HashMap,
+    env,
     fs,
     cmp::min,
 };
 
-use itertools::Itertools;
-
 use nom::{
     bytes::complete::tag,
     character::complete::{alpha1, newline},
@@ -24,109 +26,315 @@ struct ValveInfo {
     flow_rate: i32,
 }
 
-fn main() {
+/// The tunnel network reduced to what the solvers need: flow rates and the
+/// shortest travel time between every pair of valves.
+struct Network {
+    valves: Vec<Valve>,
+    neighbors: Vec<Vec<Node>>,
+    valve_to_node: HashMap<Valve, Node>,
+    flow_rates: Vec<i32>,
+    dist_matrix: DistMatrix,
+}
+
+impl Network {
+    fn new(all_valve_info: &[ValveInfo]) -> Self {
+        let valve_to_node = all_valve_info.iter()
+            .enumerate()
+            .map(|(i, v)| (v.valve.clone(), i))
+            .collect::<HashMap<String, Node>>();
+
+        let flow_rates = all_valve_info.iter()
+            .map(|v| v.flow_rate)
+            .collect::<Vec<i32>>();
+
+        let neighbors = all_valve_info.iter()
+            .map(|v| v.neighbors.iter().map(|n| valve_to_node[n]).collect())
+            .collect::<Vec<Vec<Node>>>();
+
+        let edge_list = neighbors.iter()
+            .enumerate()
+            .flat_map(|(node, ns)| ns.iter().map(move |n| (node, *n)))
+            .collect::<Vec<Edge>>();
+
+        let dist_matrix = to_distance_matrix(all_valve_info.len(), &edge_list);
+        let valves = all_valve_info.iter().map(|v| v.valve.clone()).collect();
+        Network { valves, neighbors, valve_to_node, flow_rates, dist_matrix }
+    }
+
+    fn num_nodes(&self) -> usize {
+        self.flow_rates.len()
+    }
+
+    fn node(&self, valve: &str) -> Result<Node, String> {
+        self.valve_to_node.get(valve)
+            .copied()
+            .ok_or_else(|| format!("no valve named {:?} in the input", valve))
+    }
+
+    /// Valves worth opening. Opening a valve with no flow only wastes a minute.
+    fn nodes_of_interest(&self) -> Vec<Node> {
+        (0..self.num_nodes())
+            .filter(|node| self.flow_rates[*node] > 0)
+            .collect()
+    }
+}
+
+/// Valves with nonzero flow that have been opened, one bit per entry of
+/// `Network::nodes_of_interest`.
+type ValveSet = u32;
+
+/// Keeps the table of best pressures per valve set to a few megabytes.
+const MAX_VALVES_OF_INTEREST: usize = 20;
+
+/// The most pressure a single agent can release in `minutes` for every set
+/// of valves, opening only valves from that set. Every set that is opened
+/// along some route is recorded during one search, then each entry takes
+/// the best of its subsets.
+fn best_per_valve_set(network: &Network, start: Node, minutes: i32) -> Result<Vec<i32>, String> {
+    let nodes_of_interest = network.nodes_of_interest();
+    if nodes_of_interest.len() > MAX_VALVES_OF_INTEREST {
+        return Err(format!(
+            "{} valves have a nonzero flow rate, at most {} are supported",
+            nodes_of_interest.len(), MAX_VALVES_OF_INTEREST,
+        ));
+    }
+
+    let num_sets = 1 << nodes_of_interest.len();
+    let mut best = vec![0; num_sets];
+    let mut search = Search {
+        network,
+        nodes_of_interest: &nodes_of_interest,
+        minutes,
+        route: vec![],
+        on_route: |opened: ValveSet, pressure: i32, _: &[schedule::Opening]| {
+            best[opened as usize] = best[opened as usize].max(pressure);
+        },
+    };
+    search.dfs(start, 0, 0, minutes);
+
+    for bit in 0..nodes_of_interest.len() {
+        for set in 0..num_sets {
+            if set & (1 << bit) != 0 {
+                best[set] = best[set].max(best[set ^ (1 << bit)]);
+            }
+        }
+    }
+
+    Ok(best)
+}
+
+/// The most pressure `num_agents` agents can release together, along with
+/// the valves each of them should open.
+struct Solution {
+    pressure: i32,
+    valve_sets: Vec<ValveSet>,
+}
+
+/// The most pressure `num_agents` agents can release in `minutes`, all
+/// starting at `start`. No two agents ever need to open the same valve, so
+/// the valves are split into disjoint sets, one per agent.
+fn max_pressure(network: &Network, start: Node, minutes: i32, num_agents: usize) -> Result<Solution, String> {
+    if num_agents == 0 {
+        return Err("at least one agent is needed".to_string());
+    }
+
+    let best = best_per_valve_set(network, start, minutes)?;
+    let valve_sets = split_valves(&best, num_agents);
+    let pressure = valve_sets.iter().map(|set| best[*set as usize]).sum();
+
+    Ok(Solution { pressure, valve_sets })
+}
+
+/// Splits every valve among `num_agents` agents, given the best pressure a
+/// single agent releases for each valve set.
+///
+/// `teams[k][set]` is the most pressure `k + 1` agents release opening only
+/// valves in `set`. Each team is the previous one joined by an agent that
+/// takes some subset of the valves, which costs 3^n steps for n valves. Only
+/// the last agent to join needs to look at every valve, so that split is
+/// left to the walk back through the teams.
+fn split_valves(best: &[i32], num_agents: usize) -> Vec<ValveSet> {
+    let all_valves = (best.len() - 1) as ValveSet;
+    let mut teams = vec![best.to_vec()];
+    for _ in 2..num_agents {
+        let team = teams.last().unwrap();
+        let next_team = (0..=all_valves)
+            .map(|set| best_split(best, team, set).1)
+            .collect();
+        teams.push(next_team);
+    }
+
+    let mut valve_sets = vec![];
+    let mut remaining = all_valves;
+    for team in teams.iter().take(num_agents - 1).rev() {
+        let (set, _) = best_split(best, team, remaining);
+        valve_sets.push(set);
+        remaining &= !set;
+    }
+    valve_sets.push(remaining);
+
+    valve_sets
+}
+
+/// The subset of `set` one more agent should take, leaving the rest to a
+/// team, and the pressure they release together.
+fn best_split(best: &[i32], team: &[i32], set: ValveSet) -> (ValveSet, i32) {
+    let mut best_so_far = (0, best[0] + team[set as usize]);
+    let mut subset = set;
+    while subset != 0 {
+        let pressure = best[subset as usize] + team[(set & !subset) as usize];
+        if pressure > best_so_far.1 {
+            best_so_far = (subset, pressure);
+        }
+        subset = (subset - 1) & set;
+    }
+    best_so_far
+}
+
+struct Options {
+    start: Valve,
+    minutes: Option<i32>,
+    num_agents: Option<usize>,
+    schedule: Option<schedule::Format>,
+    dot_path: Option<String>,
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options { start: "AA".to_string(), minutes: None, num_agents: None, schedule: None, dot_path: None };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let value = args.next().ok_or_else(|| format!("{} expects a value", arg))?;
+        let invalid = || format!("invalid value {:?} for {}", value, arg);
+        match arg.as_str() {
+            "--start" => options.start = value.clone(),
+            "--minutes" => options.minutes = Some(value.parse().map_err(|_| invalid())?),
+            "--agents" => options.num_agents = Some(value.parse().map_err(|_| invalid())?),
+            "--schedule" => options.schedule = Some(schedule::Format::parse(value).ok_or_else(invalid)?),
+            "--dot" => options.dot_path = Some(value.clone()),
+            _ => return Err(format!("unknown argument {:?}", arg)),
+        }
+    }
+
+    Ok(options)
+}
+
+/// Prints the most pressure that can be released, followed by the schedule
+/// that releases it when one was asked for, and returns each agent's route.
+fn solve(
+    network: &Network,
+    start: Node,
+    minutes: i32,
+    num_agents: usize,
+    options: &Options,
+    label: Option<&str>,
+) -> Result<Vec<Vec<schedule::Opening>>, String> {
+    let solution = max_pressure(network, start, minutes, num_agents)?;
+    match label {
+        Some(label) => println!("{}: {}", label, solution.pressure),
+        None => println!("{}", solution.pressure),
+    }
+
+    let routes: Vec<_> = solution.valve_sets.iter()
+        .map(|set| schedule::best_route(network, start, minutes, *set))
+        .collect();
+    match options.schedule {
+        Some(schedule::Format::Table) => print!("{}", schedule::render_table(network, &routes)),
+        Some(schedule::Format::Json) => print!("{}", schedule::render_json(network, &routes)),
+        None => (),
+    }
+
+    Ok(routes)
+}
+
+fn run(options: &Options) -> Result<(), String> {
     let input = fs::read_to_string("./src/input.txt").expect("Should be able to read input file");
     let (_, all_valve_info) = parse_all_valve_info(&input).expect("Should be able to parse input");
+    let network = Network::new(&all_valve_info);
+    let start = network.node(&options.start)?;
+
+    // the DOT export highlights the routes behind the last answer printed
+    let routes = match (options.minutes, options.num_agents) {
+        (None, None) => {
+            solve(&network, start, 30, 1, options, Some("Part 1"))?;
+            solve(&network, start, 26, 2, options, Some("Part 2"))?
+        },
+        (minutes, num_agents) => {
+            let num_agents = num_agents.unwrap_or(1);
+            // teaching the elephant takes 4 of the 30 minutes
+            let minutes = minutes.unwrap_or(if num_agents > 1 { 26 } else { 30 });
+            solve(&network, start, minutes, num_agents, options, None)?
+        },
+    };
+
+    if let Some(path) = &options.dot_path {
+        fs::write(path, dot::render_dot(&network, start, &routes)).expect("Should be able to write dot file");
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = parse_options(&args).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        eprintln!("usage: day16 [--start VALVE] [--minutes N] [--agents N] [--schedule table|json] [--dot PATH]");
+        std::process::exit(2);
+    });
+
+    if let Err(e) = run(&options) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+/// Walks every route a single agent can take, calling `on_route` with the
+/// valves opened so far, the pressure they release and the openings that
+/// got there. Both the per-set table and the schedules come from this walk.
+struct Search<'a, F> {
+    network: &'a Network,
+    nodes_of_interest: &'a [Node],
+    minutes: i32,
+    route: Vec<schedule::Opening>,
+    on_route: F,
+}
+
+impl<F: FnMut(ValveSet, i32, &[schedule::Opening])> Search<'_, F> {
+    /// Valves already in `opened` are never visited, so starting with some
+    /// bits set keeps the agent to the rest.
+    fn dfs(&mut self, node: Node, opened: ValveSet, total_pressure: i32, minutes_left: i32) {
+        (self.on_route)(opened, total_pressure, &self.route);
+
+        for (bit, next_node) in self.nodes_of_interest.iter().copied().enumerate() {
+            let distance = self.network.dist_matrix[node][next_node];
+            if opened & (1 << bit) != 0 || distance == i32::MAX {
+                continue;
+            }
+
+            let new_minutes_left = minutes_left - distance - 1; // travel there + 1 min to open the valve
+            if new_minutes_left > 0 {
+                let pressure = self.network.flow_rates[next_node] * new_minutes_left;
+                self.route.push(schedule::Opening {
+                    valve: next_node,
+                    minute: self.minutes - new_minutes_left,
+                    travel: distance,
+                    pressure,
+                });
+                self.dfs(next_node, opened | (1 << bit), total_pressure + pressure, new_minutes_left);
+                self.route.pop();
+            }
+        }
+    }
+}
 
-    let valve_to_node = all_valve_info.iter()
-        .enumerate()
-        .map(|(i, v)| (v.valve.clone(), i))
-        .collect::<HashMap<String, Node>>();
-
-    let flow_rates = all_valve_info.iter()
-        .map(|v| v.flow_rate)
-        .collect::<Vec<i32>>();
-
-    let edge_list = all_valve_info.iter()
-        .flat_map(|v| {
-            v.neighbors.iter()
-                .map(|n| (valve_to_node[&v.valve], valve_to_node[n]))
-                .collect::<Vec<Edge>>()
-        }).collect::<Vec<Edge>>();
-
-    let num_nodes = all_valve_info.len();
-    let dist_matrix = to_distance_matrix(num_nodes, &edge_list);
-    let nodes_of_interest = (0..num_nodes)
-        .filter(|node| flow_rates[*node] > 0)
-        .collect::<Vec<Node>>();
-
-    let subset_pairs = disjoint_subset_pairs(&nodes_of_interest);
-    println!("NUMBER OF PAIRS TO RUN: {}", subset_pairs.len());
-    let start_node = valve_to_node["AA"];
-
-    let part_2_ans = subset_pairs.iter()
-        .map(|(subset, compliment)| {
-            let mut visited_subset = vec![false; num_nodes];
-            let subset_ans = dfs(start_node, subset, &dist_matrix, &flow_rates, &mut visited_subset, 0, 26).unwrap();
-            let mut visited_compliment = vec![false; num_nodes];
-            let compliment_ans = dfs(start_node, compliment, &dist_matrix, &flow_rates, &mut visited_compliment, 0, 26).unwrap();
-            subset_ans + compliment_ans
-        })
-        .max().unwrap();
-
-    println!("{:?}", part_2_ans);
-}
-
-fn disjoint_subset_pairs(all_nodes: &Vec<Node>) -> Vec<(Vec<Node>, Vec<Node>)> {
-    let all_combinations = (1..=(all_nodes.len() / 2))
-        .flat_map(|i| all_nodes.iter().copied().combinations(i))
-        .map(|vec| vec.into_iter().collect::<HashSet<Node>>())
-        .collect::<Vec<HashSet<Node>>>();
-
-    let all_nodes_set = all_nodes.iter().copied().collect::<HashSet<Node>>();
-    let compliments = all_combinations.iter()
-        .map(|combination| all_nodes_set.difference(combination).copied().collect())
-        .collect::<Vec<HashSet<Node>>>();
-
-    all_combinations.iter()
-        .zip(compliments.iter())
-        .map(|(subset, compliment)| {
-            (subset.iter().copied().collect(), compliment.iter().copied().collect())    
-        })
-        .collect()
-}
-
-fn dfs(
-    node: Node,
-    nodes_of_interest: &Vec<Node>,
-    matrix: &DistMatrix,
-    flow_rates: &Vec<i32>,
-    visited: &mut Vec<bool>,
-    total_pressure: i32,
-    minutes_left: i32
-) -> Option<i32> {
-    if minutes_left <= 0 {
-        return None
-    }
-
-    let nodes_to_visit = nodes_of_interest.iter().copied()
-        .filter(|next_node| matrix[node][*next_node] < i32::MAX)
-        .filter(|next_node| !visited[*next_node])
-        .collect::<Vec<Node>>();
-
-    let best_ans_after_travelling = nodes_to_visit.iter()
-        .filter_map(|next_node| {
-            visited[*next_node] = true;
-            let new_minutes_left = minutes_left - matrix[node][*next_node] - 1; // travel there + 1 min to open the valve
-            let new_total_pressure = total_pressure + (flow_rates[*next_node] * new_minutes_left);
-            let subproblem_ans = dfs(*next_node, nodes_of_interest, matrix, flow_rates, visited, new_total_pressure, new_minutes_left);
-            visited[*next_node] = false;
-            subproblem_ans 
-        })
-        .max();
-    
-    best_ans_after_travelling.or(Some(total_pressure))
-}
-
-fn to_distance_matrix(num_nodes: usize, edge_list: &Vec<Edge>) -> DistMatrix {
+fn to_distance_matrix(num_nodes: usize, edge_list: &[Edge]) -> DistMatrix {
     let mut matrix = vec![];
     for _ in 0..num_nodes {
         matrix.push(vec![i32::MAX; num_nodes]); 
     }
 
     // Floyd-Warshall to compute all pairs shortest path
-    for node in 0..num_nodes {
-        matrix[node][node] = 0;
+    for (node, row) in matrix.iter_mut().enumerate() {
+        row[node] = 0;
     }
     for (u, v) in edge_list {
         matrix[*u][*v] = 1; 
@@ -134,10 +342,7 @@ fn to_distance_matrix(num_nodes: usize, edge_list: &Vec<Edge>) -> DistMatrix {
     for k in 0..num_nodes {
         for i in 0..num_nodes {
             for j in 0..num_nodes {
-                let new_dist = match matrix[i][k].checked_add(matrix[k][j]) {
-                    Some(i) => i,
-                    None => i32::MAX
-                };
+                let new_dist = matrix[i][k].checked_add(matrix[k][j]).unwrap_or(i32::MAX);
                 matrix[i][j] = min(matrix[i][j], new_dist);
             }
         }
@@ -170,3 +375,36 @@ fn parse_valve_info(input: &str) -> IResult<&str, ValveInfo> {
     };
     Ok((input, valve_info))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example_network() -> Network {
+        let (_, all_valve_info) = parse_all_valve_info(include_str!("input-test.txt")).unwrap();
+        Network::new(&all_valve_info)
+    }
+
+    #[test]
+    fn one_agent_matches_part_1() {
+        let network = example_network();
+        let start = network.node("AA").unwrap();
+        assert_eq!(max_pressure(&network, start, 30, 1).unwrap().pressure, 1651);
+    }
+
+    #[test]
+    fn two_agents_match_part_2() {
+        let network = example_network();
+        let start = network.node("AA").unwrap();
+        assert_eq!(max_pressure(&network, start, 26, 2).unwrap().pressure, 1707);
+    }
+
+    #[test]
+    fn more_agents_never_release_less() {
+        let network = example_network();
+        let start = network.node("AA").unwrap();
+        for num_agents in [3, 4] {
+            assert!(max_pressure(&network, start, 26, num_agents).unwrap().pressure >= 1707);
+        }
+    }
+}
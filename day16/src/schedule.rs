@@ -0,0 +1,85 @@
+use crate::{Network, Node, Search, ValveSet};
+
+/// One valve being opened by an agent.
+#[derive(Clone, Debug)]
+pub struct Opening {
+    pub valve: Node,
+    /// The minute, counting from 1, spent opening the valve. It releases
+    /// pressure from the next minute on.
+    pub minute: i32,
+    /// Minutes spent walking to the valve from the previous one.
+    pub travel: i32,
+    /// Pressure the valve releases until time runs out.
+    pub pressure: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Table,
+    Json,
+}
+
+impl Format {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "table" => Some(Format::Table),
+            "json" => Some(Format::Json),
+            _ => None,
+        }
+    }
+}
+
+/// The order in which a single agent should open the valves in `allowed`
+/// to release the most pressure.
+pub fn best_route(network: &Network, start: Node, minutes: i32, allowed: ValveSet) -> Vec<Opening> {
+    let nodes_of_interest = network.nodes_of_interest();
+    let mut best = (0, vec![]);
+    let mut search = Search {
+        network,
+        nodes_of_interest: &nodes_of_interest,
+        minutes,
+        route: vec![],
+        on_route: |_: ValveSet, pressure: i32, route: &[Opening]| {
+            if pressure > best.0 {
+                best = (pressure, route.to_vec());
+            }
+        },
+    };
+    search.dfs(start, !allowed, 0, minutes);
+    best.1
+}
+
+/// One block per agent listing the valves it opens, in order.
+pub fn render_table(network: &Network, routes: &[Vec<Opening>]) -> String {
+    let mut table = String::new();
+    for (agent, route) in routes.iter().enumerate() {
+        table.push_str(&format!("Agent {}\n", agent + 1));
+        table.push_str(&format!("{:>8}  {:<5}  {:>6}  {:>8}\n", "minute", "valve", "travel", "pressure"));
+        for opening in route {
+            table.push_str(&format!(
+                "{:>8}  {:<5}  {:>6}  {:>8}\n",
+                opening.minute, network.valves[opening.valve], opening.travel, opening.pressure,
+            ));
+        }
+        let total: i32 = route.iter().map(|o| o.pressure).sum();
+        table.push_str(&format!("{:>8}  {:<5}  {:>6}  {:>8}\n", "", "", "", total));
+    }
+    table
+}
+
+pub fn render_json(network: &Network, routes: &[Vec<Opening>]) -> String {
+    let agents: Vec<String> = routes.iter()
+        .map(|route| {
+            let openings: Vec<String> = route.iter()
+                .map(|o| format!(
+                    "        {{\"valve\": \"{}\", \"minute\": {}, \"travel\": {}, \"pressure\": {}}}",
+                    network.valves[o.valve], o.minute, o.travel, o.pressure,
+                ))
+                .collect();
+            let total: i32 = route.iter().map(|o| o.pressure).sum();
+            format!("    {{\n      \"pressure\": {},\n      \"openings\": [\n{}\n      ]\n    }}", total, openings.join(",\n"))
+        })
+        .collect();
+    let total: i32 = routes.iter().flatten().map(|o| o.pressure).sum();
+    format!("{{\n  \"pressure\": {},\n  \"agents\": [\n{}\n  ]\n}}\n", total, agents.join(",\n"))
+}
@@ -1,7 +1,11 @@
-use std::{cmp::max, collections::HashSet, fs};
+mod render;
+
+use std::{cmp::max, collections::HashSet, env, fmt, fs};
+
+use render::Window;
 
 #[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
-struct Point(i32, i32);
+pub struct Point(i32, i32);
 
 #[derive(Clone, Copy)]
 enum Move {
@@ -47,50 +51,163 @@ fn new_tail(new_head: Point, original_tail: Point) -> Point {
     }
 }
 
-fn parse_moves(input: &str) -> Vec<Move> {
+#[derive(Debug)]
+struct ParseError {
+    line: usize,
+    message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+struct Rope {
+    knots: Vec<Point>,
+    visited: HashSet<Point>,
+}
+
+impl Rope {
+    fn new(num_knots: usize) -> Self {
+        assert!(num_knots > 0, "a rope needs at least one knot");
+        let knots = vec![Point(0, 0); num_knots];
+        let visited = HashSet::from([Point(0, 0)]);
+        Rope { knots, visited }
+    }
+
+    fn apply(&mut self, motion: Move) {
+        self.knots[0] = new_head(self.knots[0], motion);
+        for i in 1..self.knots.len() {
+            self.knots[i] = new_tail(self.knots[i - 1], self.knots[i]);
+        }
+
+        self.visited.insert(self.tail());
+    }
+
+    fn knots(&self) -> &[Point] {
+        &self.knots
+    }
+
+    fn tail(&self) -> Point {
+        self.knots[self.knots.len() - 1]
+    }
+
+    fn visited(&self) -> &HashSet<Point> {
+        &self.visited
+    }
+}
+
+fn parse_moves(input: &str) -> Result<Vec<Move>, ParseError> {
     let mut moves: Vec<Move> = vec![];
-    for line in input.lines() {
-        let parts: Vec<&str> = line.split(' ').collect();
-        let c = parts[0];
-        let m = match c {
+    for (idx, line) in input.lines().enumerate() {
+        let line_number = idx + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let error = |message: String| ParseError { line: line_number, message };
+        let (direction, count) = line
+            .split_once(' ')
+            .ok_or_else(|| error(format!("expected \"<direction> <count>\", got {:?}", line)))?;
+        let m = match direction {
             "R" => Move::Right,
             "L" => Move::Left,
             "U" => Move::Up,
             "D" => Move::Down,
-            _ => Move::Down,
+            _ => return Err(error(format!("unknown direction {:?}", direction))),
         };
-        let n: i32 = parts[1].to_string().parse().unwrap();
+        let n: u32 = count
+            .parse()
+            .map_err(|_| error(format!("invalid step count {:?}", count)))?;
 
         for _ in 0..n {
             moves.push(m);
         }
     }
 
-    moves
+    Ok(moves)
+}
+
+struct Options {
+    frame_window: Option<Window>,
+    svg_path: Option<String>,
+    /// Every knot count to report on. The longest rope is the one traced.
+    knot_counts: Vec<usize>,
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options { frame_window: None, svg_path: None, knot_counts: vec![2, 10] };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} expects a value", arg));
+        match arg.as_str() {
+            "--frames" => {
+                let v = value()?;
+                options.frame_window = Some(Window::parse(v).ok_or_else(|| format!("invalid window {:?}, expected min_x,min_y,max_x,max_y", v))?);
+            },
+            "--svg" => options.svg_path = Some(value()?.clone()),
+            "--knots" => {
+                let v = value()?;
+                options.knot_counts = v.split(',')
+                    .map(|n| n.parse().ok().filter(|n| *n > 0).ok_or_else(|| format!("invalid knot count {:?}", n)))
+                    .collect::<Result<_, _>>()?;
+            },
+            _ => return Err(format!("unknown argument {:?}", arg)),
+        }
+    }
+
+    Ok(options)
+}
+
+fn trace(moves: &[Move], options: &Options) {
+    let mut rope = Rope::new(*options.knot_counts.iter().max().unwrap());
+    let mut trajectory: Vec<Vec<Point>> = vec![rope.knots().to_vec()];
+    for (step, m) in moves.iter().enumerate() {
+        rope.apply(*m);
+        if let Some(window) = &options.frame_window {
+            println!("== step {} ==", step + 1);
+            println!("{}", render::render_frame(rope.knots(), rope.visited(), window));
+        }
+        if options.svg_path.is_some() {
+            trajectory.push(rope.knots().to_vec());
+        }
+    }
+
+    if let Some(path) = &options.svg_path {
+        fs::write(path, render::render_svg(&trajectory)).expect("Should be able to write svg file");
+    }
 }
 
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = parse_options(&args).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        eprintln!("usage: day9 [--knots N,N,...] [--frames min_x,min_y,max_x,max_y] [--svg PATH]");
+        std::process::exit(2);
+    });
+
     let input = fs::read_to_string("./src/input.txt").expect("Should be able to read input file");
-    let moves = parse_moves(&input);
+    let moves = match parse_moves(&input) {
+        Ok(moves) => moves,
+        Err(e) => {
+            eprintln!("Could not parse moves: {}", e);
+            std::process::exit(1);
+        }
+    };
 
-    let mut visited: HashSet<Point> = HashSet::new();
-    visited.insert(Point(0, 0));
-    let num_knots = 10;
-    let mut knots: Vec<Point> = vec![];
-    for _ in 0..num_knots {
-        knots.push(Point(0, 0));
+    if options.frame_window.is_some() || options.svg_path.is_some() {
+        trace(&moves, &options);
     }
 
+    let mut ropes: Vec<Rope> = options.knot_counts.iter().map(|&n| Rope::new(n)).collect();
     for m in moves {
-        let new_head = new_head(knots[0], m);
-        knots[0] = new_head;
-        for i in 1..num_knots {
-            let new_knot_position = new_tail(knots[i - 1], knots[i]);
-            knots[i] = new_knot_position;
+        for rope in ropes.iter_mut() {
+            rope.apply(m);
         }
-
-        visited.insert(knots[num_knots - 1]);
     }
 
-    println!("{}", visited.len());
+    for (num_knots, rope) in options.knot_counts.iter().zip(&ropes) {
+        println!("{} knots: {}", num_knots, rope.visited().len());
+    }
 }
@@ -0,0 +1,103 @@
+use std::collections::HashSet;
+
+use crate::Point;
+
+const SVG_SCALE: i32 = 4;
+const SVG_COLOURS: [&str; 10] = [
+    "#d62728", "#ff7f0e", "#bcbd22", "#2ca02c", "#17becf",
+    "#1f77b4", "#9467bd", "#e377c2", "#8c564b", "#7f7f7f",
+];
+
+#[derive(Clone, Copy, Debug)]
+pub struct Window {
+    pub min_x: i32,
+    pub min_y: i32,
+    pub max_x: i32,
+    pub max_y: i32,
+}
+
+impl Window {
+    /// Parses a window given as `min_x,min_y,max_x,max_y`.
+    pub fn parse(s: &str) -> Option<Window> {
+        let values: Vec<i32> = s.split(',')
+            .map(|v| v.trim().parse().ok())
+            .collect::<Option<Vec<i32>>>()?;
+        match values[..] {
+            [min_x, min_y, max_x, max_y] if min_x <= max_x && min_y <= max_y => {
+                Some(Window { min_x, min_y, max_x, max_y })
+            },
+            _ => None,
+        }
+    }
+}
+
+fn knot_label(idx: usize) -> char {
+    match idx {
+        0 => 'H',
+        1..=9 => char::from_digit(idx as u32, 10).unwrap(),
+        _ => '*',
+    }
+}
+
+/// Draws one frame in the puzzle's notation. Rows are printed top to bottom,
+/// so the largest `y` comes first. When knots overlap, the one closest to
+/// the head is shown.
+pub fn render_frame(knots: &[Point], visited: &HashSet<Point>, window: &Window) -> String {
+    let mut frame = String::new();
+    for y in (window.min_y..=window.max_y).rev() {
+        for x in window.min_x..=window.max_x {
+            let point = Point(x, y);
+            let c = match knots.iter().position(|k| *k == point) {
+                Some(idx) => knot_label(idx),
+                None if point == Point(0, 0) => 's',
+                None if visited.contains(&point) => '#',
+                None => '.',
+            };
+            frame.push(c);
+        }
+        frame.push('\n');
+    }
+
+    frame
+}
+
+/// Draws the path of every knot as a polyline. `trajectory[step][knot]` is the
+/// position of `knot` after `step` moves; the first entry is the start state.
+pub fn render_svg(trajectory: &[Vec<Point>]) -> String {
+    let points = trajectory.iter().flatten();
+    let min_x = points.clone().map(|p| p.0).min().unwrap_or(0);
+    let max_x = points.clone().map(|p| p.0).max().unwrap_or(0);
+    let min_y = points.clone().map(|p| p.1).min().unwrap_or(0);
+    let max_y = points.map(|p| p.1).max().unwrap_or(0);
+
+    let width = (max_x - min_x + 2) * SVG_SCALE;
+    let height = (max_y - min_y + 2) * SVG_SCALE;
+    // svg y grows downwards, rope y grows upwards
+    let to_svg = |p: &Point| ((p.0 - min_x + 1) * SVG_SCALE, (max_y - p.1 + 1) * SVG_SCALE);
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n",
+        width, height, width, height,
+    );
+    svg.push_str(&format!("<rect width=\"{}\" height=\"{}\" fill=\"white\"/>\n", width, height));
+
+    let num_knots = trajectory.first().map_or(0, |knots| knots.len());
+    // draw the tail first so the head ends up on top
+    for knot in (0..num_knots).rev() {
+        let coords: Vec<String> = trajectory.iter()
+            .map(|knots| to_svg(&knots[knot]))
+            .map(|(x, y)| format!("{},{}", x, y))
+            .collect();
+        svg.push_str(&format!(
+            "<polyline fill=\"none\" stroke=\"{}\" stroke-width=\"1\" points=\"{}\"><title>knot {}</title></polyline>\n",
+            SVG_COLOURS[knot % SVG_COLOURS.len()],
+            coords.join(" "),
+            knot_label(knot),
+        ));
+    }
+
+    let (start_x, start_y) = to_svg(&Point(0, 0));
+    svg.push_str(&format!("<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"black\"/>\n", start_x, start_y, SVG_SCALE));
+    svg.push_str("</svg>\n");
+    svg
+}